@@ -0,0 +1,385 @@
+use std::{fmt, path::PathBuf, time::Duration};
+
+use rutiles::Column;
+
+pub const USAGE: &str = "\
+Usage: rutiles [OPTIONS] [COMMAND]
+
+Commands:
+  list                  Combined view of every block device (default)
+  sources <SOURCE>      Dump a single source: sysblock, devdisk, mounts, fstab,
+                        nvme
+  scsi                  SCSI devices with their address, host adapter and port
+  probe <DEVICE|FILE>   Detect a filesystem from its signature
+  iostat [SECS [COUNT]] I/O activity every SECS seconds (default 1),
+                        COUNT times (default 1, forever when SECS is given)
+  help                  Print this message
+
+Options:
+  -f, --format <FORMAT> Output format: text, table, json, pairs
+  -J, --json            Same as `--format json`
+  -P, --pairs           Same as `--format pairs`, `KEY=\"value\"` lines for shells
+  -o, --output <LIST>   Columns of the table, e.g. `NAME,SIZE,FSTYPE`
+                        (a leading `+` appends to the default columns)
+  -t, --topology        Same as `-o` with the `lsblk --topology` columns
+  -D, --discard         Same as `-o` with the `lsblk --discard` columns
+  -n, --noheadings      Don't print the table headings
+  -l, --list            Flat table instead of a tree
+  -d, --device <NAME>   Only show the given device or partition (repeatable),
+                        e.g. `sda1`, `/dev/sda1` or `/dev/mapper/cryptroot`
+      --hide-unused-loops
+                        Don't show loop devices that aren't bound to a file
+      --collapse-loops  Replace snap/flatpak image loops with a summary line
+                        (text and table formats only)
+  -r, --root <DIR>      Read everything relative to DIR instead of `/`
+  -v, --verbose         Log more details to stderr (repeatable), and show
+                        request queues in the text format
+  -h, --help            Print this message
+
+Columns:
+  NAME, MAJ:MIN, DISK-SEQ, SIZE, FSTYPE, LABEL, UUID, UUIDS, MOUNTPOINT, RM,
+  MEDIUM, STATE, TYPE, MODEL, VENDOR, SERIAL, WWN, REV, DRIVER, TRAN, HCTL,
+  SCSI-TYPE, HOST, HBA, HBA-ADDR, PORT, BACK-FILE, MAPPER, DM-UUID,
+  DM-TARGET, RAID-LEVEL, RAID-HEALTH, BCACHE, BCACHE-MODE, BCACHE-STATE,
+  BCACHE-DIRTY, BCACHE-HIT, NSID, EUI, NGUID, NVME-CTRL, ALIGNMENT, MIN-IO,
+  OPT-IO, PHY-SEC, LOG-SEC, ROTA, SCHED, RQ-SIZE, RA, DISC-ALN, DISC-GRAN,
+  DISC-MAX, WRITE-CACHE, DAX, ZONED, ZONE-SZ, ZONE-NR, ZONE-OMAX, ZONE-AMAX,
+  ZONE-APP, PARTN, PARTLABEL, START, END, RO, MISALIGNED, HOLDERS, SLAVES,
+  FSTAB-DEVICE, FSTAB-MOUNTPOINT, FSTAB-FSTYPE, FSTAB-OPTIONS,
+  FSTAB-FREQ, FSTAB-PASSNO
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Table,
+    Json,
+    Pairs,
+}
+
+// Each source that can be dumped with `sources`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceArg {
+    SysBlock,
+    DevDisk,
+    Mounts,
+    Fstab,
+    Nvme,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    List,
+    Sources(SourceArg),
+    Scsi,
+    Probe(String),
+    IoStat {
+        interval: Duration,
+        count: Option<u64>, // `None` means forever
+    },
+    Help,
+}
+
+#[derive(Debug)]
+pub struct Args {
+    pub command: Command,
+    pub format: OutputFormat,
+    pub columns: Option<Vec<Column>>, // `None` lets each format pick its own
+    pub headings: bool,
+    pub tree: bool,
+    pub devices: Vec<String>, // empty means every device
+    pub hide_unused_loops: bool,
+    pub collapse_loops: bool,
+    pub root: Option<PathBuf>,
+    pub verbose: u8,
+}
+
+#[derive(Debug)]
+pub struct ArgsError(String);
+
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Args {
+    // Options are accepted anywhere, before or after the command
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, ArgsError> {
+        let mut args = args.into_iter();
+        let mut positional = Vec::new();
+        // Table options imply `--format table`, unless a format was given
+        let mut format = None;
+        let mut table_options = false;
+        let mut parsed = Self {
+            command: Command::List,
+            format: OutputFormat::Text,
+            columns: None,
+            headings: true,
+            tree: true,
+            devices: Vec::new(),
+            hide_unused_loops: false,
+            collapse_loops: false,
+            root: None,
+            verbose: 0,
+        };
+
+        while let Some(arg) = args.next() {
+            // Support both `--opt value` and `--opt=value`
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value)),
+                _ => (arg.clone(), None),
+            };
+            let mut value = |name: &str| {
+                inline
+                    .map(str::to_string)
+                    .or_else(|| args.next())
+                    .ok_or_else(|| ArgsError(format!("`{name}` requires a value")))
+            };
+
+            match flag.as_str() {
+                "-f" | "--format" => format = Some(parse_format(&value(&flag)?)?),
+                "-J" | "--json" => format = Some(OutputFormat::Json),
+                "-P" | "--pairs" => format = Some(OutputFormat::Pairs),
+                "-o" | "--output" => {
+                    parsed.columns = Some(parse_columns(&value(&flag)?)?);
+                    table_options = true;
+                }
+                "-t" | "--topology" => {
+                    parsed.columns = Some(Column::TOPOLOGY.to_vec());
+                    table_options = true;
+                }
+                "-D" | "--discard" => {
+                    parsed.columns = Some(Column::DISCARD.to_vec());
+                    table_options = true;
+                }
+                "-n" | "--noheadings" => {
+                    parsed.headings = false;
+                    table_options = true;
+                }
+                "-l" | "--list" => {
+                    parsed.tree = false;
+                    table_options = true;
+                }
+                "-d" | "--device" => parsed.devices.push(value(&flag)?),
+                "--hide-unused-loops" => parsed.hide_unused_loops = true,
+                "--collapse-loops" => parsed.collapse_loops = true,
+                "-r" | "--root" => parsed.root = Some(PathBuf::from(value(&flag)?)),
+                "-v" | "--verbose" => parsed.verbose = parsed.verbose.saturating_add(1),
+                "-h" | "--help" => parsed.command = Command::Help,
+                "--" => positional.extend(args.by_ref()),
+                // Allow `-vv`
+                short
+                    if short.len() > 2
+                        && short.starts_with('-')
+                        && short[1..].bytes().all(|b| b == b'v') =>
+                {
+                    parsed.verbose = parsed.verbose.saturating_add((short.len() - 1) as u8);
+                }
+                unknown if unknown.starts_with('-') && unknown.len() > 1 => {
+                    return Err(ArgsError(format!("unknown option `{unknown}`")));
+                }
+                _ => positional.push(arg),
+            }
+        }
+
+        if parsed.command != Command::Help {
+            parsed.command = parse_command(positional)?;
+        }
+
+        parsed.format = match format {
+            Some(format) => format,
+            None if table_options => OutputFormat::Table,
+            None => OutputFormat::Text,
+        };
+
+        // The summary line has no place in JSON nor pairs, scripts would
+        //     silently lose the collapsed devices
+        if parsed.collapse_loops
+            && matches!(parsed.format, OutputFormat::Json | OutputFormat::Pairs)
+        {
+            return Err(ArgsError(
+                "`--collapse-loops` only applies to the text and table formats".into(),
+            ));
+        }
+
+        Ok(parsed)
+    }
+}
+
+fn parse_command(positional: Vec<String>) -> Result<Command, ArgsError> {
+    let mut positional = positional.into_iter();
+    let command = match positional.next().as_deref() {
+        None | Some("list") => Command::List,
+        Some("help") => Command::Help,
+        Some("scsi") => Command::Scsi,
+        Some("sources") => {
+            let source = positional.next().ok_or_else(|| {
+                ArgsError(
+                    "`sources` requires one of: sysblock, devdisk, mounts, fstab, nvme".into(),
+                )
+            })?;
+            Command::Sources(parse_source(&source)?)
+        }
+        Some("probe") => {
+            let target = positional
+                .next()
+                .ok_or_else(|| ArgsError("`probe` requires a device or a file".into()))?;
+            Command::Probe(target)
+        }
+        Some("iostat") => {
+            let interval = positional.next();
+            let count = match positional.next() {
+                Some(count) => Some(parse_number(&count, "count")?),
+                None if interval.is_some() => None,
+                None => Some(1),
+            };
+            let interval = match interval {
+                Some(secs) => Duration::from_secs(parse_number(&secs, "interval")?),
+                None => Duration::from_secs(1),
+            };
+            Command::IoStat { interval, count }
+        }
+        Some(other) => return Err(ArgsError(format!("unknown command `{other}`"))),
+    };
+
+    if let Some(extra) = positional.next() {
+        return Err(ArgsError(format!("unexpected argument `{extra}`")));
+    }
+
+    Ok(command)
+}
+
+// Strictly positive, e.g. an interval or a count
+fn parse_number(value: &str, what: &str) -> Result<u64, ArgsError> {
+    value
+        .parse()
+        .ok()
+        .filter(|&n| n > 0)
+        .ok_or_else(|| ArgsError(format!("invalid {what} `{value}`")))
+}
+
+fn parse_format(format: &str) -> Result<OutputFormat, ArgsError> {
+    match format {
+        "text" => Ok(OutputFormat::Text),
+        "table" => Ok(OutputFormat::Table),
+        "json" => Ok(OutputFormat::Json),
+        "pairs" => Ok(OutputFormat::Pairs),
+        other => Err(ArgsError(format!("unknown format `{other}`"))),
+    }
+}
+
+fn parse_source(source: &str) -> Result<SourceArg, ArgsError> {
+    match source {
+        "sysblock" => Ok(SourceArg::SysBlock),
+        "devdisk" => Ok(SourceArg::DevDisk),
+        "mounts" => Ok(SourceArg::Mounts),
+        "fstab" => Ok(SourceArg::Fstab),
+        "nvme" => Ok(SourceArg::Nvme),
+        other => Err(ArgsError(format!("unknown source `{other}`"))),
+    }
+}
+
+fn parse_columns(list: &str) -> Result<Vec<Column>, ArgsError> {
+    let (mut columns, list) = match list.strip_prefix('+') {
+        Some(extra) => (Column::DEFAULT.to_vec(), extra),
+        None => (Vec::new(), list),
+    };
+
+    for name in list.split(',').filter(|name| !name.is_empty()) {
+        let column =
+            Column::from_name(name).ok_or_else(|| ArgsError(format!("unknown column `{name}`")))?;
+        columns.push(column);
+    }
+
+    if columns.is_empty() {
+        return Err(ArgsError("no columns given".into()));
+    }
+
+    Ok(columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, ArgsError> {
+        Args::parse(args.iter().map(|arg| (*arg).to_string()))
+    }
+
+    #[test]
+    fn defaults_to_listing_as_text() {
+        let args = parse(&[]).unwrap();
+        assert_eq!(args.command, Command::List);
+        assert_eq!(args.format, OutputFormat::Text);
+        assert!(args.columns.is_none());
+        assert!(args.headings && args.tree);
+    }
+
+    #[test]
+    fn accepts_options_anywhere() {
+        let args = parse(&["-d", "sda", "sources", "--root=/mnt", "sysblock", "-vv"]).unwrap();
+        assert_eq!(args.command, Command::Sources(SourceArg::SysBlock));
+        assert_eq!(args.devices, ["sda"]);
+        assert_eq!(args.root, Some(PathBuf::from("/mnt")));
+        assert_eq!(args.verbose, 2);
+    }
+
+    #[test]
+    fn table_options_imply_the_table_format() {
+        let args = parse(&["-o", "+MODEL", "-n"]).unwrap();
+        assert_eq!(args.format, OutputFormat::Table);
+        assert!(!args.headings);
+        let columns = args.columns.unwrap();
+        assert_eq!(columns.len(), Column::DEFAULT.len() + 1);
+        assert_eq!(columns.last(), Some(&Column::Model));
+
+        // An explicit format wins
+        let args = parse(&["-o", "NAME", "--json"]).unwrap();
+        assert_eq!(args.format, OutputFormat::Json);
+    }
+
+    #[test]
+    fn parses_iostat_interval_and_count() {
+        let iostat = |args: &[&str]| parse(args).unwrap().command;
+        assert_eq!(
+            iostat(&["iostat"]),
+            Command::IoStat {
+                interval: Duration::from_secs(1),
+                count: Some(1),
+            }
+        );
+        assert_eq!(
+            iostat(&["iostat", "5"]),
+            Command::IoStat {
+                interval: Duration::from_secs(5),
+                count: None,
+            }
+        );
+        assert_eq!(
+            iostat(&["iostat", "2", "3"]),
+            Command::IoStat {
+                interval: Duration::from_secs(2),
+                count: Some(3),
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_arguments() {
+        for args in [
+            &["--bogus"][..],
+            &["sources"],
+            &["sources", "nowhere"],
+            &["probe"],
+            &["list", "extra"],
+            &["-o", "NOPE"],
+            &["-f", "xml"],
+            &["iostat", "0"],
+            &["--device"],
+            &["--collapse-loops", "--json"],
+        ] {
+            assert!(parse(args).is_err(), "{args:?} was accepted");
+        }
+    }
+}
@@ -0,0 +1,851 @@
+use super::combined::{CombinedDeviceInfo, CombinedPartitionInfo, readable_size_from};
+use super::devnum::DevNum;
+use super::fstab::Fstab;
+use super::sys_block::{
+    BcacheInfo, DeviceKind, DmInfo, HardwareIdentity, LoopInfo, MdInfo, MediumState, Misalignment,
+    NvmeNamespace, PartitionGeometry, QueueInfo, ScsiInfo, ZonedInfo,
+};
+
+// Selectable columns, named after their `lsblk -o` counterpart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Name,
+    MajMin,
+    DiskSeq,
+    Size,
+    FsType,
+    Label,
+    Uuid,
+    MountPoint,
+    Rm,
+    Medium,
+    State,
+    Type,
+    Model,
+    Vendor,
+    Serial,
+    Wwn,
+    Rev,
+    Driver,
+    Tran,
+    Hctl,
+    ScsiType,
+    Host,
+    Hba,
+    HbaAddr,
+    Port,
+    BackFile,
+    Mapper,
+    DmUuid,
+    DmTarget,
+    RaidLevel,
+    RaidHealth,
+    Bcache,
+    BcacheMode,
+    BcacheState,
+    BcacheDirty,
+    BcacheHit,
+    Nsid,
+    Eui,
+    Nguid,
+    NvmeCtrl,
+    Alignment,
+    MinIo,
+    OptIo,
+    PhySec,
+    LogSec,
+    Rota,
+    Sched,
+    RqSize,
+    Ra,
+    DiscAln,
+    DiscGran,
+    DiscMax,
+    WriteCache,
+    Dax,
+    Zoned,
+    ZoneSz,
+    ZoneNr,
+    ZoneOmax,
+    ZoneAmax,
+    ZoneApp,
+    Partn,
+    PartLabel,
+    Start,
+    End,
+    Ro,
+    Misaligned,
+    Holders,
+    Slaves,
+    Uuids,
+    FstabDevice,
+    FstabMountPoint,
+    FstabFsType,
+    FstabOptions,
+    FstabFreq,
+    FstabPassNo,
+}
+
+impl Column {
+    pub const ALL: &[Self] = &[
+        Self::Name,
+        Self::MajMin,
+        Self::DiskSeq,
+        Self::Size,
+        Self::FsType,
+        Self::Label,
+        Self::Uuid,
+        Self::MountPoint,
+        Self::Rm,
+        Self::Medium,
+        Self::State,
+        Self::Type,
+        Self::Model,
+        Self::Vendor,
+        Self::Serial,
+        Self::Wwn,
+        Self::Rev,
+        Self::Driver,
+        Self::Tran,
+        Self::Hctl,
+        Self::ScsiType,
+        Self::Host,
+        Self::Hba,
+        Self::HbaAddr,
+        Self::Port,
+        Self::BackFile,
+        Self::Mapper,
+        Self::DmUuid,
+        Self::DmTarget,
+        Self::RaidLevel,
+        Self::RaidHealth,
+        Self::Bcache,
+        Self::BcacheMode,
+        Self::BcacheState,
+        Self::BcacheDirty,
+        Self::BcacheHit,
+        Self::Nsid,
+        Self::Eui,
+        Self::Nguid,
+        Self::NvmeCtrl,
+        Self::Alignment,
+        Self::MinIo,
+        Self::OptIo,
+        Self::PhySec,
+        Self::LogSec,
+        Self::Rota,
+        Self::Sched,
+        Self::RqSize,
+        Self::Ra,
+        Self::DiscAln,
+        Self::DiscGran,
+        Self::DiscMax,
+        Self::WriteCache,
+        Self::Dax,
+        Self::Zoned,
+        Self::ZoneSz,
+        Self::ZoneNr,
+        Self::ZoneOmax,
+        Self::ZoneAmax,
+        Self::ZoneApp,
+        Self::Partn,
+        Self::PartLabel,
+        Self::Start,
+        Self::End,
+        Self::Ro,
+        Self::Misaligned,
+        Self::Holders,
+        Self::Slaves,
+        Self::Uuids,
+        Self::FstabDevice,
+        Self::FstabMountPoint,
+        Self::FstabFsType,
+        Self::FstabOptions,
+        Self::FstabFreq,
+        Self::FstabPassNo,
+    ];
+
+    // Used when no columns are given
+    pub const DEFAULT: &[Self] = &[
+        Self::Name,
+        Self::MajMin,
+        Self::Rm,
+        Self::Size,
+        Self::Type,
+        Self::FsType,
+        Self::MountPoint,
+    ];
+
+    // Same as `lsblk --topology`
+    pub const TOPOLOGY: &[Self] = &[
+        Self::Name,
+        Self::Alignment,
+        Self::MinIo,
+        Self::OptIo,
+        Self::PhySec,
+        Self::LogSec,
+        Self::Rota,
+        Self::Sched,
+        Self::RqSize,
+        Self::Ra,
+    ];
+
+    // Same as `lsblk --scsi`, plus the host adapter and port
+    pub const SCSI: &[Self] = &[
+        Self::Name,
+        Self::Hctl,
+        Self::ScsiType,
+        Self::Vendor,
+        Self::Model,
+        Self::Rev,
+        Self::Serial,
+        Self::Tran,
+        Self::Hba,
+        Self::HbaAddr,
+        Self::Port,
+    ];
+
+    // Same as `lsblk --discard`
+    pub const DISCARD: &[Self] = &[Self::Name, Self::DiscAln, Self::DiscGran, Self::DiscMax];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Name => "NAME",
+            Self::MajMin => "MAJ:MIN",
+            Self::DiskSeq => "DISK-SEQ",
+            Self::Size => "SIZE",
+            Self::FsType => "FSTYPE",
+            Self::Label => "LABEL",
+            Self::Uuid => "UUID",
+            Self::MountPoint => "MOUNTPOINT",
+            Self::Rm => "RM",
+            Self::Medium => "MEDIUM",
+            Self::State => "STATE",
+            Self::Type => "TYPE",
+            Self::Model => "MODEL",
+            Self::Vendor => "VENDOR",
+            Self::Serial => "SERIAL",
+            Self::Wwn => "WWN",
+            Self::Rev => "REV",
+            Self::Driver => "DRIVER",
+            Self::Tran => "TRAN",
+            Self::Hctl => "HCTL",
+            Self::ScsiType => "SCSI-TYPE",
+            Self::Host => "HOST",
+            Self::Hba => "HBA",
+            Self::HbaAddr => "HBA-ADDR",
+            Self::Port => "PORT",
+            Self::BackFile => "BACK-FILE",
+            Self::Mapper => "MAPPER",
+            Self::DmUuid => "DM-UUID",
+            Self::DmTarget => "DM-TARGET",
+            Self::RaidLevel => "RAID-LEVEL",
+            Self::RaidHealth => "RAID-HEALTH",
+            Self::Bcache => "BCACHE",
+            Self::BcacheMode => "BCACHE-MODE",
+            Self::BcacheState => "BCACHE-STATE",
+            Self::BcacheDirty => "BCACHE-DIRTY",
+            Self::BcacheHit => "BCACHE-HIT",
+            Self::Nsid => "NSID",
+            Self::Eui => "EUI",
+            Self::Nguid => "NGUID",
+            Self::NvmeCtrl => "NVME-CTRL",
+            Self::Alignment => "ALIGNMENT",
+            Self::MinIo => "MIN-IO",
+            Self::OptIo => "OPT-IO",
+            Self::PhySec => "PHY-SEC",
+            Self::LogSec => "LOG-SEC",
+            Self::Rota => "ROTA",
+            Self::Sched => "SCHED",
+            Self::RqSize => "RQ-SIZE",
+            Self::Ra => "RA",
+            Self::DiscAln => "DISC-ALN",
+            Self::DiscGran => "DISC-GRAN",
+            Self::DiscMax => "DISC-MAX",
+            Self::WriteCache => "WRITE-CACHE",
+            Self::Dax => "DAX",
+            Self::Zoned => "ZONED",
+            Self::ZoneSz => "ZONE-SZ",
+            Self::ZoneNr => "ZONE-NR",
+            Self::ZoneOmax => "ZONE-OMAX",
+            Self::ZoneAmax => "ZONE-AMAX",
+            Self::ZoneApp => "ZONE-APP",
+            Self::Partn => "PARTN",
+            Self::PartLabel => "PARTLABEL",
+            Self::Start => "START",
+            Self::End => "END",
+            Self::Ro => "RO",
+            Self::Misaligned => "MISALIGNED",
+            Self::Holders => "HOLDERS",
+            Self::Slaves => "SLAVES",
+            Self::Uuids => "UUIDS",
+            Self::FstabDevice => "FSTAB-DEVICE",
+            Self::FstabMountPoint => "FSTAB-MOUNTPOINT",
+            Self::FstabFsType => "FSTAB-FSTYPE",
+            Self::FstabOptions => "FSTAB-OPTIONS",
+            Self::FstabFreq => "FSTAB-FREQ",
+            Self::FstabPassNo => "FSTAB-PASSNO",
+        }
+    }
+
+    // Same as `name`, but usable as a shell variable (e.g. `FSTAB_OPTIONS`)
+    pub fn key(self) -> String {
+        self.name().replace(['-', ':'], "_")
+    }
+
+    // Case insensitive, so `-o name,size` works too
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|column| {
+            column.name().eq_ignore_ascii_case(name) || column.key().eq_ignore_ascii_case(name)
+        })
+    }
+
+    // Numbers are right aligned
+    pub fn is_numeric(self) -> bool {
+        matches!(
+            self,
+            Self::Size
+                | Self::Rm
+                | Self::Nsid
+                | Self::Alignment
+                | Self::MinIo
+                | Self::OptIo
+                | Self::PhySec
+                | Self::LogSec
+                | Self::Rota
+                | Self::RqSize
+                | Self::Ra
+                | Self::DiscAln
+                | Self::DiscGran
+                | Self::DiscMax
+                | Self::Dax
+                | Self::ZoneSz
+                | Self::ZoneNr
+                | Self::ZoneOmax
+                | Self::ZoneAmax
+                | Self::ZoneApp
+                | Self::DiskSeq
+                | Self::Partn
+                | Self::Start
+                | Self::End
+                | Self::Ro
+                | Self::FstabFreq
+                | Self::FstabPassNo
+        )
+    }
+
+    // Empty when the information is not available
+    pub fn value(self, row: Row<'_>) -> String {
+        let or_empty = |value: Option<&String>| value.cloned().unwrap_or_default();
+        let fstab = |field: fn(&Fstab) -> String| row.fstab_entry().map(field).unwrap_or_default();
+        let queue = |field: fn(&QueueInfo) -> String| row.queue().map(field).unwrap_or_default();
+        let geometry =
+            |field: fn(&PartitionGeometry) -> String| row.geometry().map(field).unwrap_or_default();
+        let identity = |field: fn(&HardwareIdentity) -> Option<&String>| {
+            or_empty(row.identity().and_then(field))
+        };
+        let scsi = |field: fn(&ScsiInfo) -> String| row.scsi().map(field).unwrap_or_default();
+        let bcache = |field: fn(&BcacheInfo) -> String| row.bcache().map(field).unwrap_or_default();
+        let zoned = |field: fn(&ZonedInfo) -> String| row.zoned().map(field).unwrap_or_default();
+        let flag = |flag: bool| if flag { "1" } else { "0" }.to_string();
+
+        match self {
+            Self::Name => row.name().to_string(),
+            Self::MajMin => row.devnum().map(|d| d.to_string()).unwrap_or_default(),
+            Self::DiskSeq => row.diskseq().map(|s| s.to_string()).unwrap_or_default(),
+            Self::Size => row.size().map(readable_size_from).unwrap_or_default(),
+            Self::FsType => or_empty(row.filesystem()),
+            Self::Label => or_empty(row.label()),
+            Self::Uuid => or_empty(row.uuids().and_then(|uuids| uuids.first())),
+            Self::MountPoint => or_empty(row.mount_point()),
+            Self::Rm => row.removable().map(flag).unwrap_or_default(),
+            Self::Medium => row.medium().map(|m| flag(m.present)).unwrap_or_default(),
+            Self::State => or_empty(row.medium().and_then(|m| m.device_state.as_ref())),
+            Self::Type => row.kind().to_string(),
+            Self::Model => or_empty(row.model()),
+            Self::Vendor => identity(|i| i.vendor.as_ref()),
+            Self::Serial => identity(|i| i.serial.as_ref()),
+            Self::Wwn => identity(|i| i.wwid.as_ref()),
+            Self::Rev => identity(|i| i.revision.as_ref()),
+            Self::Driver => identity(|i| i.driver.as_ref()),
+            Self::Tran => row
+                .identity()
+                .and_then(|i| i.transport)
+                .map(|t| t.to_string())
+                .unwrap_or_default(),
+            Self::Hctl => scsi(|s| s.address.to_string()),
+            Self::ScsiType => scsi(|s| s.type_name().unwrap_or_default().to_string()),
+            Self::Host => scsi(|s| s.host.clone()),
+            Self::Hba => scsi(|s| s.host_driver.clone().unwrap_or_default()),
+            Self::HbaAddr => scsi(|s| s.adapter.clone().unwrap_or_default()),
+            Self::Port => scsi(|s| s.port.clone().unwrap_or_default()),
+            Self::BackFile => or_empty(row.loop_info().and_then(|l| l.backing_file.as_ref())),
+            Self::Mapper => row.dm_info().map(DmInfo::mapper_path).unwrap_or_default(),
+            Self::DmUuid => or_empty(row.dm_info().and_then(|dm| dm.uuid.as_ref())),
+            Self::DmTarget => row
+                .dm_info()
+                .and_then(|dm| dm.target)
+                .map(|target| target.to_string())
+                .unwrap_or_default(),
+            Self::RaidLevel => or_empty(row.md_info().and_then(|md| md.level.as_ref())),
+            Self::RaidHealth => row
+                .md_info()
+                .map(|md| md.health().to_string())
+                .unwrap_or_default(),
+            Self::Bcache => bcache(|b| b.role.to_string()),
+            Self::BcacheMode => bcache(|b| b.cache_mode.clone().unwrap_or_default()),
+            Self::BcacheState => bcache(|b| b.state.clone().unwrap_or_default()),
+            Self::BcacheDirty => bcache(|b| b.dirty_data.clone().unwrap_or_default()),
+            Self::BcacheHit => bcache(|b| {
+                b.stats
+                    .map(|s| format!("{}%", s.hit_ratio))
+                    .unwrap_or_default()
+            }),
+            Self::Nsid => row
+                .nvme()
+                .and_then(|n| n.nsid)
+                .map(|nsid| nsid.to_string())
+                .unwrap_or_default(),
+            Self::Eui => or_empty(row.nvme().and_then(|n| n.eui.as_ref())),
+            Self::Nguid => or_empty(row.nvme().and_then(|n| n.nguid.as_ref())),
+            Self::NvmeCtrl => row
+                .nvme()
+                .map(|n| {
+                    let names: Vec<&str> = n.controllers.iter().map(|c| c.name.as_str()).collect();
+                    names.join(",")
+                })
+                .unwrap_or_default(),
+            Self::Alignment => queue(|q| q.alignment_offset.to_string()),
+            Self::MinIo => queue(|q| q.minimum_io_size.to_string()),
+            Self::OptIo => queue(|q| q.optimal_io_size.to_string()),
+            Self::PhySec => queue(|q| q.physical_block_size.to_string()),
+            Self::LogSec => queue(|q| q.logical_block_size.to_string()),
+            Self::Rota => row.queue().map(|q| flag(q.rotational)).unwrap_or_default(),
+            Self::Sched => queue(|q| q.scheduler.clone().unwrap_or_default()),
+            Self::RqSize => queue(|q| q.nr_requests.to_string()),
+            Self::Ra => queue(|q| q.read_ahead_kb.to_string()),
+            Self::DiscAln => queue(|q| q.discard_alignment.to_string()),
+            Self::DiscGran => queue(|q| readable_size_from(q.discard_granularity)),
+            Self::DiscMax => queue(|q| readable_size_from(q.discard_max_bytes)),
+            Self::WriteCache => queue(|q| q.write_cache.clone().unwrap_or_default()),
+            Self::Dax => row.queue().map(|q| flag(q.dax)).unwrap_or_default(),
+            // Conventional devices are `none`, like `lsblk`
+            Self::Zoned => row.zoned().map_or("none", |z| z.model.as_str()).to_string(),
+            Self::ZoneSz => zoned(|z| readable_size_from(z.zone_size)),
+            Self::ZoneNr => zoned(|z| z.nr_zones.to_string()),
+            Self::ZoneOmax => zoned(|z| z.max_open_zones.to_string()),
+            Self::ZoneAmax => zoned(|z| z.max_active_zones.to_string()),
+            Self::ZoneApp => zoned(|z| readable_size_from(z.zone_append_max_bytes)),
+            Self::Partn => geometry(|g| g.number.map(|n| n.to_string()).unwrap_or_default()),
+            Self::PartLabel => or_empty(row.partname()),
+            Self::Start => geometry(|g| g.start.to_string()),
+            Self::End => geometry(|g| g.end.to_string()),
+            Self::Ro => row.read_only().map(flag).unwrap_or_default(),
+            Self::Misaligned => row
+                .misalignment()
+                .map(|m| m.as_str().to_string())
+                .unwrap_or_default(),
+            Self::Holders => row.holders().join(","),
+            Self::Slaves => row.slaves().join(","),
+            Self::Uuids => row.uuids().map(|uuids| uuids.join(",")).unwrap_or_default(),
+            Self::FstabDevice => fstab(|e| e.device.clone()),
+            Self::FstabMountPoint => fstab(|e| e.mount_point.clone()),
+            Self::FstabFsType => fstab(|e| e.fs_type.clone()),
+            Self::FstabOptions => fstab(|e| e.options.join(",")),
+            Self::FstabFreq => fstab(|e| e.dump_freq.to_string()),
+            Self::FstabPassNo => fstab(|e| e.fsck_pass.to_string()),
+        }
+    }
+}
+
+// A single line of output, either a device or one of its partitions
+#[derive(Debug, Clone, Copy)]
+pub enum Row<'a> {
+    Device(&'a CombinedDeviceInfo),
+    Partition(&'a CombinedPartitionInfo),
+}
+
+impl<'a> Row<'a> {
+    pub fn name(self) -> &'a str {
+        match self {
+            Self::Device(d) => &d.name,
+            Self::Partition(p) => &p.name,
+        }
+    }
+
+    pub fn kind(self) -> DeviceKind {
+        match self {
+            Self::Device(d) => d.kind,
+            Self::Partition(_) => DeviceKind::Partition,
+        }
+    }
+
+    pub fn devnum(self) -> Option<DevNum> {
+        match self {
+            Self::Device(d) => d.devnum,
+            Self::Partition(p) => p.devnum,
+        }
+    }
+
+    pub fn diskseq(self) -> Option<u64> {
+        match self {
+            Self::Device(d) => d.diskseq,
+            Self::Partition(_) => None,
+        }
+    }
+
+    pub fn partname(self) -> Option<&'a String> {
+        match self {
+            Self::Device(_) => None,
+            Self::Partition(p) => p.partname.as_ref(),
+        }
+    }
+
+    pub fn model(self) -> Option<&'a String> {
+        match self {
+            Self::Device(d) => d.model.as_ref(),
+            Self::Partition(_) => None,
+        }
+    }
+
+    pub fn identity(self) -> Option<&'a HardwareIdentity> {
+        match self {
+            Self::Device(d) => d.identity.as_ref(),
+            Self::Partition(_) => None,
+        }
+    }
+
+    pub fn scsi(self) -> Option<&'a ScsiInfo> {
+        match self {
+            Self::Device(d) => d.scsi.as_ref(),
+            Self::Partition(_) => None,
+        }
+    }
+
+    pub fn loop_info(self) -> Option<&'a LoopInfo> {
+        match self {
+            Self::Device(d) => d.loop_info.as_ref(),
+            Self::Partition(_) => None,
+        }
+    }
+
+    pub fn dm_info(self) -> Option<&'a DmInfo> {
+        match self {
+            Self::Device(d) => d.dm_info.as_ref(),
+            Self::Partition(_) => None,
+        }
+    }
+
+    pub fn bcache(self) -> Option<&'a BcacheInfo> {
+        match self {
+            Self::Device(d) => d.bcache.as_ref(),
+            Self::Partition(p) => p.bcache.as_ref(),
+        }
+    }
+
+    pub fn md_info(self) -> Option<&'a MdInfo> {
+        match self {
+            Self::Device(d) => d.md_info.as_ref(),
+            Self::Partition(_) => None,
+        }
+    }
+
+    pub fn nvme(self) -> Option<&'a NvmeNamespace> {
+        match self {
+            Self::Device(d) => d.nvme.as_ref(),
+            Self::Partition(_) => None,
+        }
+    }
+
+    pub fn zoned(self) -> Option<&'a ZonedInfo> {
+        match self {
+            Self::Device(d) => d.zoned.as_ref(),
+            Self::Partition(_) => None,
+        }
+    }
+
+    pub fn queue(self) -> Option<&'a QueueInfo> {
+        match self {
+            Self::Device(d) => d.queue.as_ref(),
+            Self::Partition(p) => p.queue.as_ref(),
+        }
+    }
+
+    pub fn geometry(self) -> Option<&'a PartitionGeometry> {
+        match self {
+            Self::Device(_) => None,
+            Self::Partition(p) => p.geometry.as_ref(),
+        }
+    }
+
+    pub fn misalignment(self) -> Option<Misalignment> {
+        match self {
+            Self::Device(_) => None,
+            Self::Partition(p) => p.misalignment(),
+        }
+    }
+
+    pub fn holders(self) -> &'a [String] {
+        match self {
+            Self::Device(d) => &d.holders,
+            Self::Partition(p) => &p.holders,
+        }
+    }
+
+    pub fn slaves(self) -> &'a [String] {
+        match self {
+            Self::Device(d) => &d.slaves,
+            Self::Partition(p) => &p.slaves,
+        }
+    }
+
+    pub fn size(self) -> Option<u64> {
+        match self {
+            Self::Device(d) => d.size,
+            Self::Partition(p) => p.size,
+        }
+    }
+
+    pub fn filesystem(self) -> Option<&'a String> {
+        match self {
+            Self::Device(d) => d.filesystem.as_ref(),
+            Self::Partition(p) => p.filesystem.as_ref(),
+        }
+    }
+
+    pub fn label(self) -> Option<&'a String> {
+        match self {
+            Self::Device(d) => d.label.as_ref(),
+            Self::Partition(p) => p.label.as_ref(),
+        }
+    }
+
+    pub fn mount_point(self) -> Option<&'a String> {
+        match self {
+            Self::Device(d) => d.mount_point.as_ref(),
+            Self::Partition(p) => p.mount_point.as_ref(),
+        }
+    }
+
+    pub fn medium(self) -> Option<&'a MediumState> {
+        match self {
+            Self::Device(d) => d.medium.as_ref(),
+            Self::Partition(_) => None,
+        }
+    }
+
+    pub fn read_only(self) -> Option<bool> {
+        match self {
+            Self::Device(d) => d.medium.as_ref().map(|m| m.read_only),
+            Self::Partition(p) => p.geometry.map(|g| g.read_only),
+        }
+    }
+
+    pub fn removable(self) -> Option<bool> {
+        match self {
+            Self::Device(d) => d.removable,
+            Self::Partition(p) => p.removable,
+        }
+    }
+
+    pub fn uuids(self) -> Option<&'a Vec<String>> {
+        match self {
+            Self::Device(d) => d.uuids.as_ref(),
+            Self::Partition(p) => p.uuids.as_ref(),
+        }
+    }
+
+    pub fn fstab_entry(self) -> Option<&'a Fstab> {
+        match self {
+            Self::Device(d) => d.fstab_entry.as_ref(),
+            Self::Partition(p) => p.fstab_entry.as_ref(),
+        }
+    }
+}
+
+// Every device followed by its partitions, with their depth in the tree
+pub fn rows(devices: &[CombinedDeviceInfo]) -> Vec<(Row<'_>, usize)> {
+    let mut rows = Vec::new();
+    for device in devices {
+        rows.push((Row::Device(device), 0));
+        for part in &device.partitions {
+            rows.push((Row::Partition(part), 1));
+        }
+    }
+    rows
+}
+
+// Aligned table, like `lsblk` without `-P`/`-J`
+#[derive(Debug, Clone)]
+pub struct Table {
+    pub columns: Vec<Column>,
+    pub headings: bool, // `--noheadings` turns it off
+    pub tree: bool,     // indent children under their parent, `--list` turns it off
+}
+
+impl Table {
+    pub fn render(&self, devices: &[CombinedDeviceInfo]) -> String {
+        let rows = rows(devices);
+
+        // Render every cell first, so we know how wide each column is
+        let mut lines: Vec<Vec<String>> = Vec::new();
+        if self.headings {
+            lines.push(self.columns.iter().map(|c| c.name().to_string()).collect());
+        }
+        for (i, (row, depth)) in rows.iter().enumerate() {
+            let cells = self
+                .columns
+                .iter()
+                .map(|column| {
+                    let value = column.value(*row);
+                    if *column == Column::Name && self.tree && *depth > 0 {
+                        // Last child when the next row goes back to its parent's level
+                        let last = rows.get(i + 1).is_none_or(|(_, next)| next < depth);
+                        format!("{}{value}", if last { "└─" } else { "├─" })
+                    } else {
+                        value
+                    }
+                })
+                .collect();
+            lines.push(cells);
+        }
+
+        let widths: Vec<usize> = (0..self.columns.len())
+            .map(|i| {
+                lines
+                    .iter()
+                    .map(|cells| cells[i].chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let mut out = String::new();
+        for cells in &lines {
+            let mut line = String::new();
+            for (i, (cell, column)) in cells.iter().zip(&self.columns).enumerate() {
+                if i > 0 {
+                    line.push(' ');
+                }
+                let pad = " ".repeat(widths[i] - cell.chars().count());
+                if column.is_numeric() {
+                    line.push_str(&pad);
+                    line.push_str(cell);
+                } else {
+                    line.push_str(cell);
+                    line.push_str(&pad);
+                }
+            }
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+        out
+    }
+}
+
+// `KEY="value"` pairs, one line per device or partition, like `lsblk -P`
+// Values are meant to be `eval`ed by a shell: `"`, `\`, `$` and `` ` `` are
+//     escaped with a backslash and control characters as `\xNN`
+pub fn pairs(columns: &[Column], devices: &[CombinedDeviceInfo]) -> String {
+    let mut out = String::new();
+    for (row, _) in rows(devices) {
+        let line: Vec<String> = columns
+            .iter()
+            .map(|column| format!("{}=\"{}\"", column.key(), escape(&column.value(row))))
+            .collect();
+        out.push_str(&line.join(" "));
+        out.push('\n');
+    }
+    out
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' | '\\' | '$' | '`' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c if c.is_control() => escaped.push_str(&format!("\\x{:02x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dev_disk::DevDiskInfo;
+    use crate::fixture::Fixture;
+    use crate::fstab::FstabInfo;
+    use crate::proc_mounts::ProcMountsInfo;
+    use crate::sys_block::SysBlockInfo;
+
+    // `sda` with two partitions, then `sdb` without any
+    fn devices() -> Vec<CombinedDeviceInfo> {
+        let fixture = Fixture::new("columns-table");
+        for (name, sectors) in [("sda", "2097152"), ("sdb", "100")] {
+            fixture
+                .file(&format!("sys/block/{name}/size"), sectors)
+                .file(&format!("sys/block/{name}/removable"), "0\n");
+        }
+        for (name, sectors) in [("sda1", "1048576"), ("sda2", "1024")] {
+            fixture
+                .file(&format!("sys/block/sda/{name}/size"), sectors)
+                .file(
+                    &format!("sys/block/sda/{name}/uevent"),
+                    "DEVTYPE=partition\n",
+                );
+        }
+
+        let paths = fixture.paths();
+        CombinedDeviceInfo::new(
+            &paths,
+            &SysBlockInfo::new(&paths).unwrap(),
+            &DevDiskInfo::default(),
+            &ProcMountsInfo::default(),
+            &FstabInfo::default(),
+        )
+    }
+
+    fn table(headings: bool, tree: bool) -> Table {
+        Table {
+            columns: vec![Column::Name, Column::Size, Column::Type],
+            headings,
+            tree,
+        }
+    }
+
+    #[test]
+    fn renders_an_aligned_tree() {
+        let expected = "\
+NAME    SIZE TYPE
+sda      1GB disk
+├─sda1 512MB part
+└─sda2 512KB part
+sdb     50KB disk
+";
+        assert_eq!(table(true, true).render(&devices()), expected);
+    }
+
+    #[test]
+    fn renders_a_list_without_headings() {
+        let expected = "\
+sda    1GB disk
+sda1 512MB part
+sda2 512KB part
+sdb   50KB disk
+";
+        assert_eq!(table(false, false).render(&devices()), expected);
+    }
+
+    #[test]
+    fn escapes_values_for_shells() {
+        assert_eq!(escape("my root"), "my root");
+        assert_eq!(escape(r#"a"b\c"#), r#"a\"b\\c"#);
+        assert_eq!(escape("$HOME `id`"), r"\$HOME \`id\`");
+        assert_eq!(escape("tab\there\n"), r"tab\x09here\x0a");
+    }
+}
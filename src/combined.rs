@@ -1,15 +1,22 @@
 use std::fmt;
-use tracing::warn;
 
 use super::dev_disk::DevDiskInfo;
+use super::devnum::DevNum;
 use super::fstab::{Fstab, FstabInfo};
-use super::magic::get_fstype_with_magic;
+use super::graph::{Node, StorageGraph};
+use super::paths::SysPaths;
 use super::proc_mounts::ProcMountsInfo;
-use super::sys_block::SysBlockInfo;
+use super::sys_block::{
+    AlignmentOffset, BcacheInfo, BcacheRole, DeviceKind, DmInfo, HardwareIdentity, LoopInfo,
+    MdInfo, MediumState, Misalignment, NvmeNamespace, PartitionGeometry, QueueInfo, ScsiInfo,
+    SysBlockInfo, ZonedInfo,
+};
 
 #[derive(Debug)]
 pub struct CombinedPartitionInfo {
     pub name: String,
+    pub devnum: Option<DevNum>,
+    pub partname: Option<String>,
     pub size: Option<u64>,
     pub filesystem: Option<String>,
     pub label: Option<String>,
@@ -17,154 +24,177 @@ pub struct CombinedPartitionInfo {
     pub removable: Option<bool>,
     pub uuids: Option<Vec<String>>,
     pub fstab_entry: Option<Fstab>,
+    pub queue: Option<QueueInfo>,
+    pub bcache: Option<BcacheInfo>,
+    pub geometry: Option<PartitionGeometry>,
+    pub holders: Vec<String>,
+    pub slaves: Vec<String>,
 }
 
 #[derive(Debug)]
 pub struct CombinedDeviceInfo {
     pub name: String,
+    pub kind: DeviceKind,
+    pub devnum: Option<DevNum>,
+    pub diskseq: Option<u64>,
     pub model: Option<String>,
+    pub identity: Option<HardwareIdentity>,
+    pub scsi: Option<ScsiInfo>,
     pub size: Option<u64>,
     pub filesystem: Option<String>,
     pub label: Option<String>,
     pub mount_point: Option<String>,
     pub removable: Option<bool>,
+    pub medium: Option<MediumState>,
     pub uuids: Option<Vec<String>>,
     pub fstab_entry: Option<Fstab>,
+    pub loop_info: Option<LoopInfo>,
+    pub dm_info: Option<DmInfo>,
+    pub md_info: Option<MdInfo>,
+    pub bcache: Option<BcacheInfo>,
+    pub nvme: Option<NvmeNamespace>,
+    pub zoned: Option<ZonedInfo>,
+    pub queue: Option<QueueInfo>,
+    pub holders: Vec<String>,
+    pub slaves: Vec<String>,
     pub partitions: Vec<CombinedPartitionInfo>,
 }
 
 impl CombinedDeviceInfo {
     pub fn new(
+        paths: &SysPaths,
         sys_block: &SysBlockInfo,
         dev_disk: &DevDiskInfo,
         proc_mounts: &ProcMountsInfo,
         fstab: &FstabInfo,
     ) -> Vec<Self> {
-        let mut combined_info = Vec::new();
-
-        // Start with the information from `/sys/block`
-        for sys_block in &sys_block.info {
-            let mut combined_device = Self {
-                name: sys_block.name.clone(),
-                model: Some(sys_block.info.model.clone()),
-                size: Some(sys_block.info.size),
-                filesystem: None,
-                label: None,
-                mount_point: None,
-                removable: Some(sys_block.info.removable),
-                uuids: None,
-                fstab_entry: None,
-                partitions: Vec::new(),
-            };
-
-            // Add information from `/dev/disk`
-            if let Some(dev_disk) = dev_disk.info.iter().find(|d| d.name == sys_block.name) {
-                combined_device.label.clone_from(&dev_disk.label);
-                combined_device.uuids.clone_from(&dev_disk.uuid);
-            }
+        let graph = StorageGraph::new(paths, sys_block, dev_disk, proc_mounts, fstab);
+        Self::from_graph(&graph)
+    }
 
-            // Add information from `/proc/mounts`
-            if let Some(proc_mounts) = proc_mounts.info.iter().find(|d| d.name == sys_block.name) {
-                combined_device.mount_point = Some(proc_mounts.mount_point.clone());
-                combined_device.filesystem = Some(proc_mounts.fstype.clone());
-            }
+    // Two-level view of the graph: every node that isn't a partition is
+    //     listed as a device, along with its own partitions
+    // Nodes are already sorted by name, so devices and partitions are too
+    pub fn from_graph(graph: &StorageGraph) -> Vec<Self> {
+        graph
+            .nodes
+            .iter()
+            .filter(|node| node.kind != DeviceKind::Partition)
+            .map(|node| {
+                let info = node.info.clone();
+                let partitions = node
+                    .children
+                    .iter()
+                    .map(|&id| graph.node(id))
+                    .filter(|child| child.kind == DeviceKind::Partition)
+                    .map(CombinedPartitionInfo::from_node)
+                    .collect();
 
-            // Fallback to magic numbers to find filesystem type
-            if combined_device.filesystem.is_none() && sys_block.part.is_none()
-            // && is_running_with_sudo()
-            {
-                combined_device.filesystem = match get_fstype_with_magic(&sys_block.name) {
-                    Ok(fs_type) => fs_type,
-                    Err(e) => {
-                        warn!("Failed to get fstype from signature: {e}");
-                        None
-                    }
+                Self {
+                    name: node.name.clone(),
+                    kind: node.kind,
+                    devnum: info.devnum,
+                    diskseq: info.diskseq,
+                    model: info.model,
+                    identity: info.identity,
+                    scsi: info.scsi,
+                    size: info.size,
+                    filesystem: info.filesystem,
+                    label: info.label,
+                    mount_point: info.mount_point,
+                    removable: info.removable,
+                    medium: info.medium,
+                    uuids: info.uuids,
+                    fstab_entry: info.fstab_entry,
+                    loop_info: info.loop_info,
+                    dm_info: info.dm_info,
+                    md_info: info.md_info,
+                    bcache: info.bcache,
+                    nvme: info.nvme,
+                    zoned: info.zoned,
+                    queue: info.queue,
+                    holders: info.holders,
+                    slaves: info.slaves,
+                    partitions,
                 }
-            }
+            })
+            .collect()
+    }
+}
 
-            // Try to find a matching fstab entry
-            let get_dev_fstab_entry = fstab.info.iter().find(|entry| {
-                combined_device // by UUID
-                    .uuids
-                    .as_ref()
-                    .and_then(|uuids| uuids.first())
-                    .map(|uuid| format!("UUID={}", uuid) == entry.device)
-                    .unwrap_or(false)
-                    || combined_device // by label
-                        .label
-                        .as_ref()
-                        .map(|label| format!("LABEL={}", label) == entry.device)
-                        .unwrap_or(false)
-            });
-            if let Some(fstab_entry) = get_dev_fstab_entry {
-                combined_device.fstab_entry = Some(fstab_entry.clone());
-            }
+impl CombinedDeviceInfo {
+    // A loop device that isn't bound to any file
+    pub fn is_unused_loop(&self) -> bool {
+        self.kind == DeviceKind::Loop && self.loop_info.is_none()
+    }
 
-            // Same thing for partitions...
-            if let Some(parts) = &sys_block.part {
-                for part in parts {
-                    let mut combined_partition = CombinedPartitionInfo {
-                        name: part.name.clone(),
-                        size: Some(part.info.size),
-                        filesystem: None,
-                        label: None,
-                        mount_point: None,
-                        removable: Some(part.info.removable),
-                        uuids: None,
-                        fstab_entry: None,
-                    };
-
-                    if let Some(dev_part) = dev_disk.info.iter().find(|d| d.name == part.name) {
-                        combined_partition.label.clone_from(&dev_part.label);
-                        combined_partition.uuids.clone_from(&dev_part.uuid);
-                    }
-
-                    if let Some(proc_part) = proc_mounts.info.iter().find(|d| d.name == part.name) {
-                        combined_partition.mount_point = Some(proc_part.mount_point.clone());
-                        combined_partition.filesystem = Some(proc_part.fstype.clone());
-                    }
-
-                    let get_part_fstab_entry = fstab.info.iter().find(|entry| {
-                        combined_partition // by UUID
-                            .uuids
-                            .as_ref()
-                            .and_then(|uuids| uuids.first())
-                            .map(|uuid| format!("UUID={}", uuid) == entry.device)
-                            .unwrap_or(false)
-                            || combined_partition // by label
-                                .label
-                                .as_ref()
-                                .map(|label| format!("LABEL={}", label) == entry.device)
-                                .unwrap_or(false)
-                    });
-                    if let Some(fstab_entry) = get_part_fstab_entry {
-                        combined_partition.fstab_entry = Some(fstab_entry.clone());
-                    }
-
-                    if combined_partition.filesystem.is_none() {
-                        combined_partition.filesystem = match get_fstype_with_magic(&part.name) {
-                            Ok(fs_type) => fs_type,
-                            Err(e) => {
-                                warn!("Failed to get fstype from signature: {e}");
-                                None
-                            }
-                        }
-                    }
-
-                    combined_device.partitions.push(combined_partition);
-                }
-            }
+    // A conventional filesystem on a host-managed zoned device, e.g. `ext4`
+    pub fn zone_unaware_filesystem(&self) -> Option<&str> {
+        let filesystem = self.filesystem.as_deref()?;
+        self.zoned
+            .as_ref()
+            .is_some_and(|zoned| zoned.rejects_filesystem(filesystem))
+            .then_some(filesystem)
+    }
 
-            // Sort partitions
-            combined_device
-                .partitions
-                .sort_by(|a, b| a.name.cmp(&b.name));
-            combined_info.push(combined_device);
+    // A loop device holding a read-only image (e.g. a snap)
+    pub fn is_image_loop(&self) -> bool {
+        self.kind == DeviceKind::Loop
+            && (self.filesystem.as_deref() == Some("squashfs")
+                || self.loop_info.as_ref().is_some_and(LoopInfo::is_image))
+    }
+}
+
+// What's left of the loop devices removed by `collapse_image_loops`
+#[derive(Debug, Default)]
+pub struct LoopSummary {
+    pub count: usize,
+    pub size: u64,
+}
+
+// Machines with snaps or flatpaks can have dozens of image loop devices,
+//     so they can be replaced by a single summary line
+pub fn collapse_image_loops(devices: &mut Vec<CombinedDeviceInfo>) -> LoopSummary {
+    let mut summary = LoopSummary::default();
+    devices.retain(|device| {
+        if device.is_image_loop() {
+            summary.count += 1;
+            summary.size += device.size.unwrap_or(0);
+            false
+        } else {
+            true
+        }
+    });
+    summary
+}
+
+impl CombinedPartitionInfo {
+    fn from_node(node: &Node) -> Self {
+        let info = node.info.clone();
+        Self {
+            name: node.name.clone(),
+            devnum: info.devnum,
+            partname: info.partname,
+            size: info.size,
+            filesystem: info.filesystem,
+            label: info.label,
+            mount_point: info.mount_point,
+            removable: info.removable,
+            uuids: info.uuids,
+            fstab_entry: info.fstab_entry,
+            queue: info.queue,
+            bcache: info.bcache,
+            geometry: info.geometry,
+            holders: info.holders,
+            slaves: info.slaves,
         }
+    }
+}
 
-        // Sort devices
-        combined_info.sort_by(|a, b| a.name.cmp(&b.name));
-        combined_info
+impl CombinedPartitionInfo {
+    // Checked against the queue of its device
+    pub fn misalignment(&self) -> Option<Misalignment> {
+        self.geometry?.misalignment(self.queue.as_ref()?)
     }
 }
 
@@ -174,14 +204,25 @@ impl fmt::Display for CombinedDeviceInfo {
         let indent = "  "; // 2 spaces for indentation
 
         // Device-specific field
+        writeln!(f, "{indent}• Type: {}", self.kind)?;
         if let Some(model) = &self.model {
             writeln!(f, "{indent}• Model: {model}")?;
         }
+        if let Some(identity) = &self.identity {
+            format_identity(f, indent, identity)?;
+        }
+        if let Some(scsi) = &self.scsi {
+            format_scsi(f, indent, scsi)?;
+        }
+        if let Some(diskseq) = self.diskseq {
+            writeln!(f, "{indent}• Disk Sequence: {diskseq}")?;
+        }
 
         // Common fields
         format_common_fields(
             f,
             indent,
+            self.devnum,
             self.size,
             self.filesystem.as_ref(),
             self.label.as_ref(),
@@ -190,6 +231,61 @@ impl fmt::Display for CombinedDeviceInfo {
             self.uuids.as_ref(),
             self.fstab_entry.as_ref(),
         )?;
+        format_relations(f, indent, &self.holders, &self.slaves)?;
+
+        // Medium section
+        if let Some(medium) = &self.medium {
+            format_medium(f, indent, medium)?;
+        }
+
+        // Loop devices section
+        if let Some(loop_info) = &self.loop_info {
+            format_loop_info(f, indent, loop_info)?;
+        }
+
+        // Device-mapper section
+        if let Some(dm_info) = &self.dm_info {
+            format_dm_info(f, indent, dm_info)?;
+        }
+
+        // NVMe section
+        if let Some(nvme) = &self.nvme {
+            format_nvme(f, indent, nvme)?;
+        }
+
+        // Zoned section
+        if let Some(zoned) = &self.zoned {
+            format_zoned(f, indent, zoned)?;
+        }
+        if let Some(filesystem) = self.zone_unaware_filesystem() {
+            writeln!(
+                f,
+                "{indent}⚠ Zoned: {filesystem} isn't zone-aware, it can't be used on a host-managed device"
+            )?;
+        }
+
+        // Queue section, only in the alternate form (`{:#}`, from `-v`) since
+        //     it's as long as everything else
+        if let Some(queue) = &self.queue {
+            if f.alternate() {
+                format_queue(f, indent, queue)?;
+            } else if queue.alignment_offset == AlignmentOffset::Misaligned {
+                writeln!(
+                    f,
+                    "{indent}⚠ Alignment: can't be aligned with the devices it's stacked on"
+                )?;
+            }
+        }
+
+        // Software RAID section
+        if let Some(md_info) = &self.md_info {
+            format_md_info(f, indent, md_info)?;
+        }
+
+        // bcache section
+        if let Some(bcache) = &self.bcache {
+            format_bcache(f, indent, bcache)?;
+        }
 
         // Partition section
         if !self.partitions.is_empty() {
@@ -212,6 +308,7 @@ impl fmt::Display for CombinedPartitionInfo {
         format_common_fields(
             f,
             indent,
+            self.devnum,
             self.size,
             self.filesystem.as_ref(),
             self.label.as_ref(),
@@ -220,15 +317,106 @@ impl fmt::Display for CombinedPartitionInfo {
             self.uuids.as_ref(),
             self.fstab_entry.as_ref(),
         )?;
+        format_relations(f, indent, &self.holders, &self.slaves)?;
+
+        // Geometry section
+        if let Some(geometry) = &self.geometry {
+            if let Some(number) = geometry.number {
+                writeln!(f, "{indent}• Number: {number}")?;
+            }
+            if let Some(partname) = &self.partname {
+                writeln!(f, "{indent}• Partition Name: {partname}")?;
+            }
+            writeln!(
+                f,
+                "{indent}• Sectors: {} - {}",
+                geometry.start, geometry.end
+            )?;
+            if geometry.read_only {
+                writeln!(f, "{indent}• Read Only: Yes")?;
+            }
+        }
+        if let Some(misalignment) = self.misalignment() {
+            writeln!(f, "{indent}⚠ Misaligned: {misalignment}")?;
+        }
+        if let Some(bcache) = &self.bcache {
+            format_bcache(f, indent, bcache)?;
+        }
 
         Ok(())
     }
 }
 
+// Devices grouped under the SCSI host they hang off, for `rutiles scsi`
+// Devices that aren't behind a SCSI host are left out
+#[derive(Debug)]
+pub struct ScsiHosts<'a> {
+    pub devices: &'a [CombinedDeviceInfo],
+}
+
+impl fmt::Display for ScsiHosts<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut devices: Vec<(&CombinedDeviceInfo, &ScsiInfo)> = self
+            .devices
+            .iter()
+            .filter_map(|device| Some((device, device.scsi.as_ref()?)))
+            .collect();
+        devices.sort_by_key(|(_, scsi)| scsi.address);
+
+        let mut host = None;
+        for (device, scsi) in devices {
+            if host != Some(scsi.address.host) {
+                host = Some(scsi.address.host);
+                writeln!(f)?; // Extra line
+                write!(f, "⛊ {}", scsi.host)?;
+                format_adapter(f, scsi)?;
+                writeln!(f)?;
+            }
+
+            writeln!(f, "    ⛉ {}", device.name)?;
+            let indent = "      ";
+            writeln!(f, "{indent}• HCTL: {}", scsi.address)?;
+            if let Some(type_name) = scsi.type_name() {
+                writeln!(f, "{indent}• Type: {type_name}")?;
+            }
+            let identity = device.identity.clone().unwrap_or_default();
+            let fields = [
+                ("Vendor", identity.vendor.as_ref()),
+                ("Model", device.model.as_ref()),
+                ("Revision", identity.revision.as_ref()),
+                ("Serial", identity.serial.as_ref()),
+                ("Port", scsi.port.as_ref()),
+            ];
+            for (name, value) in fields {
+                if let Some(value) = value {
+                    writeln!(f, "{indent}• {name}: {value}")?;
+                }
+            }
+            if let Some(transport) = identity.transport {
+                writeln!(f, "{indent}• Transport: {transport}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for LoopSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "⛶ {} image loop devices collapsed ({} in total)",
+            self.count,
+            readable_size_from(self.size)
+        )
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 fn format_common_fields(
     f: &mut fmt::Formatter<'_>,
     indent: &str,
+    devnum: Option<DevNum>,
     size: Option<u64>,
     filesystem: Option<&String>,
     label: Option<&String>,
@@ -237,6 +425,9 @@ fn format_common_fields(
     uuids: Option<&Vec<String>>,
     fstab_entry: Option<&Fstab>,
 ) -> fmt::Result {
+    if let Some(devnum) = devnum {
+        writeln!(f, "{indent}• Device Number: {devnum}")?;
+    }
     if let Some(size) = size {
         writeln!(f, "{indent}• Size: {}", readable_size_from(size))?;
     }
@@ -298,7 +489,396 @@ fn format_common_fields(
     Ok(())
 }
 
-fn readable_size_from(size: u64) -> String {
+fn format_identity(
+    f: &mut fmt::Formatter<'_>,
+    indent: &str,
+    identity: &HardwareIdentity,
+) -> fmt::Result {
+    let fields = [
+        ("Vendor", identity.vendor.as_ref()),
+        ("Serial", identity.serial.as_ref()),
+        ("WWN", identity.wwid.as_ref()),
+        ("Revision", identity.revision.as_ref()),
+        ("Driver", identity.driver.as_ref()),
+    ];
+    for (name, value) in fields {
+        if let Some(value) = value {
+            writeln!(f, "{indent}• {name}: {value}")?;
+        }
+    }
+    if let Some(transport) = identity.transport {
+        writeln!(f, "{indent}• Transport: {transport}")?;
+    }
+    Ok(())
+}
+
+// e.g. `• SCSI: 0:0:0:0 disk, on host0 (ahci, 0000:00:17.0) via ata1`
+fn format_scsi(f: &mut fmt::Formatter<'_>, indent: &str, scsi: &ScsiInfo) -> fmt::Result {
+    write!(f, "{indent}• SCSI: {}", scsi.address)?;
+    if let Some(type_name) = scsi.type_name() {
+        write!(f, " {type_name}")?;
+    }
+    write!(f, ", on {}", scsi.host)?;
+    format_adapter(f, scsi)?;
+    if let Some(port) = &scsi.port {
+        write!(f, " via {port}")?;
+    }
+    writeln!(f)
+}
+
+// Host driver and PCI address, e.g. ` (ahci, 0000:00:17.0)`
+fn format_adapter(f: &mut fmt::Formatter<'_>, scsi: &ScsiInfo) -> fmt::Result {
+    let adapter: Vec<&str> = [scsi.host_driver.as_deref(), scsi.adapter.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect();
+    if adapter.is_empty() {
+        return Ok(());
+    }
+    write!(f, " ({})", adapter.join(", "))
+}
+
+// Stacked devices, e.g. a partition held by `dm-0`
+fn format_relations(
+    f: &mut fmt::Formatter<'_>,
+    indent: &str,
+    holders: &[String],
+    slaves: &[String],
+) -> fmt::Result {
+    if !holders.is_empty() {
+        writeln!(f, "{indent}• Held By: {}", holders.join(", "))?;
+    }
+    if !slaves.is_empty() {
+        writeln!(f, "{indent}• Built On: {}", slaves.join(", "))?;
+    }
+    Ok(())
+}
+
+fn format_loop_info(f: &mut fmt::Formatter<'_>, indent: &str, loop_info: &LoopInfo) -> fmt::Result {
+    let extra_indent = "  ";
+    let yes_no = |flag: bool| if flag { "Yes" } else { "No" };
+
+    writeln!(f, "{indent}• Loop:")?;
+    if let Some(backing_file) = &loop_info.backing_file {
+        writeln!(f, "{indent}{extra_indent}• Backing File: {backing_file}")?;
+    }
+    writeln!(f, "{indent}{extra_indent}• Offset: {}", loop_info.offset)?;
+    writeln!(
+        f,
+        "{indent}{extra_indent}• Size Limit: {}",
+        loop_info.sizelimit
+    )?;
+    writeln!(
+        f,
+        "{indent}{extra_indent}• Autoclear: {}",
+        yes_no(loop_info.autoclear)
+    )?;
+    writeln!(
+        f,
+        "{indent}{extra_indent}• Partition Scan: {}",
+        yes_no(loop_info.partscan)
+    )?;
+    writeln!(
+        f,
+        "{indent}{extra_indent}• Direct I/O: {}",
+        yes_no(loop_info.dio)
+    )?;
+    Ok(())
+}
+
+fn format_dm_info(f: &mut fmt::Formatter<'_>, indent: &str, dm_info: &DmInfo) -> fmt::Result {
+    let extra_indent = "  ";
+
+    writeln!(f, "{indent}• Device Mapper:")?;
+    writeln!(f, "{indent}{extra_indent}• Name: {}", dm_info.name)?;
+    writeln!(f, "{indent}{extra_indent}• Path: {}", dm_info.mapper_path())?;
+    if let Some(target) = dm_info.target {
+        writeln!(f, "{indent}{extra_indent}• Target: {target}")?;
+    }
+    if let Some(uuid) = &dm_info.uuid {
+        writeln!(f, "{indent}{extra_indent}• UUID: {uuid}")?;
+    }
+    writeln!(
+        f,
+        "{indent}{extra_indent}• Suspended: {}",
+        if dm_info.suspended { "Yes" } else { "No" }
+    )?;
+    Ok(())
+}
+
+// Members only point at the bcache device, which holds the details
+fn format_bcache(f: &mut fmt::Formatter<'_>, indent: &str, bcache: &BcacheInfo) -> fmt::Result {
+    let extra_indent = "  ";
+    let list = |names: &[String]| {
+        if names.is_empty() {
+            "none".to_string()
+        } else {
+            names.join(", ")
+        }
+    };
+
+    match bcache.role {
+        BcacheRole::Backing => {
+            return writeln!(
+                f,
+                "{indent}• bcache: backing device of {}, cached by {}",
+                list(&bcache.devices),
+                list(&bcache.caches)
+            );
+        }
+        BcacheRole::Cache => {
+            return writeln!(
+                f,
+                "{indent}• bcache: caching {} (backed by {})",
+                list(&bcache.devices),
+                list(&bcache.backing)
+            );
+        }
+        BcacheRole::Device => {}
+    }
+
+    writeln!(f, "{indent}• bcache:")?;
+    writeln!(
+        f,
+        "{indent}{extra_indent}• Backing: {}",
+        list(&bcache.backing)
+    )?;
+    match &bcache.cache_set {
+        Some(set) => writeln!(
+            f,
+            "{indent}{extra_indent}• Cache: {} (set {set})",
+            list(&bcache.caches)
+        )?,
+        None => writeln!(f, "{indent}{extra_indent}• Cache: none, detached")?,
+    }
+    let fields = [
+        ("Mode", bcache.cache_mode.as_ref()),
+        ("State", bcache.state.as_ref()),
+        ("Dirty Data", bcache.dirty_data.as_ref()),
+        ("Sequential Cutoff", bcache.sequential_cutoff.as_ref()),
+    ];
+    for (name, value) in fields {
+        if let Some(value) = value {
+            writeln!(f, "{indent}{extra_indent}• {name}: {value}")?;
+        }
+    }
+    if let Some(stats) = &bcache.stats {
+        writeln!(
+            f,
+            "{indent}{extra_indent}• Hit Ratio: {}% ({} hits, {} misses)",
+            stats.hit_ratio, stats.hits, stats.misses
+        )?;
+        if stats.bypass_hits > 0 || stats.bypass_misses > 0 {
+            writeln!(
+                f,
+                "{indent}{extra_indent}• Bypassed: {} hits, {} misses",
+                stats.bypass_hits, stats.bypass_misses
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn format_md_info(f: &mut fmt::Formatter<'_>, indent: &str, md_info: &MdInfo) -> fmt::Result {
+    let extra_indent = "  ";
+
+    writeln!(f, "{indent}• RAID:")?;
+    if let Some(level) = &md_info.level {
+        writeln!(f, "{indent}{extra_indent}• Level: {level}")?;
+    }
+    writeln!(f, "{indent}{extra_indent}• Health: {}", md_info.health())?;
+    if let Some(array_state) = &md_info.array_state {
+        writeln!(f, "{indent}{extra_indent}• Array State: {array_state}")?;
+    }
+    if let Some(raid_disks) = md_info.raid_disks {
+        let missing = md_info.degraded.unwrap_or(0);
+        writeln!(
+            f,
+            "{indent}{extra_indent}• Disks: {} of {raid_disks}",
+            raid_disks.saturating_sub(missing)
+        )?;
+    }
+    if md_info.is_syncing()
+        && let Some(action) = &md_info.sync_action
+    {
+        match md_info.sync_progress() {
+            Some(progress) => writeln!(f, "{indent}{extra_indent}• Sync: {action} {progress:.1}%")?,
+            None => writeln!(f, "{indent}{extra_indent}• Sync: {action}")?,
+        }
+    }
+    if let Some(mismatch_cnt) = md_info.mismatch_cnt
+        && mismatch_cnt > 0
+    {
+        writeln!(f, "{indent}{extra_indent}• Mismatches: {mismatch_cnt}")?;
+    }
+    if !md_info.members.is_empty() {
+        writeln!(f, "{indent}{extra_indent}• Members:")?;
+        for member in &md_info.members {
+            match member.slot {
+                Some(slot) => writeln!(
+                    f,
+                    "{indent}{extra_indent}{extra_indent}• {} (slot {slot}, {})",
+                    member.name,
+                    member.role()
+                )?,
+                None => writeln!(
+                    f,
+                    "{indent}{extra_indent}{extra_indent}• {} ({})",
+                    member.name,
+                    member.role()
+                )?,
+            }
+        }
+    }
+    Ok(())
+}
+
+fn format_nvme(f: &mut fmt::Formatter<'_>, indent: &str, nvme: &NvmeNamespace) -> fmt::Result {
+    let extra_indent = "  ";
+
+    writeln!(f, "{indent}• NVMe Namespace:")?;
+    if let Some(nsid) = nvme.nsid {
+        writeln!(f, "{indent}{extra_indent}• NSID: {nsid}")?;
+    }
+    if let Some(eui) = &nvme.eui {
+        writeln!(f, "{indent}{extra_indent}• EUI: {eui}")?;
+    }
+    if let Some(nguid) = &nvme.nguid {
+        writeln!(f, "{indent}{extra_indent}• NGUID: {nguid}")?;
+    }
+    if let Some(uuid) = &nvme.uuid {
+        writeln!(f, "{indent}{extra_indent}• UUID: {uuid}")?;
+    }
+    for controller in &nvme.controllers {
+        let details: Vec<&str> = [&controller.transport, &controller.state]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        if details.is_empty() {
+            writeln!(f, "{indent}{extra_indent}• Controller: {}", controller.name)?;
+        } else {
+            writeln!(
+                f,
+                "{indent}{extra_indent}• Controller: {} ({})",
+                controller.name,
+                details.join(", ")
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn format_medium(f: &mut fmt::Formatter<'_>, indent: &str, medium: &MediumState) -> fmt::Result {
+    if !medium.present {
+        writeln!(f, "{indent}• Medium: None, the drive is empty")?;
+    }
+    if medium.read_only {
+        writeln!(f, "{indent}• Read Only: Yes")?;
+    }
+    if !medium.events.is_empty() {
+        writeln!(f, "{indent}• Media Events: {}", medium.events.join(", "))?;
+    }
+    if medium.is_offline() {
+        let state = medium.device_state.as_deref().unwrap_or_default();
+        writeln!(f, "{indent}⚠ State: {state}, the device doesn't accept I/O")?;
+    }
+    Ok(())
+}
+
+fn format_zoned(f: &mut fmt::Formatter<'_>, indent: &str, zoned: &ZonedInfo) -> fmt::Result {
+    let extra_indent = "  ";
+    let limit = |value: u64| {
+        if value == 0 {
+            "no limit".to_string()
+        } else {
+            value.to_string()
+        }
+    };
+
+    writeln!(f, "{indent}• Zoned: {}", zoned.model)?;
+    writeln!(f, "{indent}{extra_indent}• Zones: {}", zoned.nr_zones)?;
+    writeln!(
+        f,
+        "{indent}{extra_indent}• Zone Size: {}",
+        readable_size_from(zoned.zone_size)
+    )?;
+    writeln!(
+        f,
+        "{indent}{extra_indent}• Max Open Zones: {}",
+        limit(zoned.max_open_zones)
+    )?;
+    writeln!(
+        f,
+        "{indent}{extra_indent}• Max Active Zones: {}",
+        limit(zoned.max_active_zones)
+    )?;
+    if zoned.zone_append_max_bytes > 0 {
+        writeln!(
+            f,
+            "{indent}{extra_indent}• Zone Append: up to {}",
+            readable_size_from(zoned.zone_append_max_bytes)
+        )?;
+    }
+    Ok(())
+}
+
+fn format_queue(f: &mut fmt::Formatter<'_>, indent: &str, queue: &QueueInfo) -> fmt::Result {
+    let extra_indent = "  ";
+    let yes_no = |flag: bool| if flag { "Yes" } else { "No" };
+
+    writeln!(f, "{indent}• Queue:")?;
+    writeln!(
+        f,
+        "{indent}{extra_indent}• Sector Size: {}B logical, {}B physical",
+        queue.logical_block_size, queue.physical_block_size
+    )?;
+    writeln!(
+        f,
+        "{indent}{extra_indent}• I/O Size: {}B minimum, {}B optimal",
+        queue.minimum_io_size, queue.optimal_io_size
+    )?;
+    match queue.alignment_offset {
+        AlignmentOffset::Bytes(offset) => {
+            writeln!(f, "{indent}{extra_indent}• Alignment Offset: {offset}")?;
+        }
+        AlignmentOffset::Misaligned => writeln!(
+            f,
+            "{indent}{extra_indent}• Alignment Offset: -1, the devices below can't be aligned"
+        )?,
+    }
+    writeln!(
+        f,
+        "{indent}{extra_indent}• Rotational: {}",
+        yes_no(queue.rotational)
+    )?;
+    if let Some(scheduler) = &queue.scheduler {
+        writeln!(f, "{indent}{extra_indent}• Scheduler: {scheduler}")?;
+    }
+    writeln!(
+        f,
+        "{indent}{extra_indent}• Read Ahead: {}KB",
+        queue.read_ahead_kb
+    )?;
+    writeln!(f, "{indent}{extra_indent}• Requests: {}", queue.nr_requests)?;
+    if queue.discard_granularity > 0 {
+        writeln!(
+            f,
+            "{indent}{extra_indent}• Discard: {} granularity, {} max",
+            readable_size_from(queue.discard_granularity),
+            readable_size_from(queue.discard_max_bytes)
+        )?;
+    } else {
+        writeln!(f, "{indent}{extra_indent}• Discard: not supported")?;
+    }
+    if let Some(write_cache) = &queue.write_cache {
+        writeln!(f, "{indent}{extra_indent}• Write Cache: {write_cache}")?;
+    }
+    writeln!(f, "{indent}{extra_indent}• DAX: {}", yes_no(queue.dax))?;
+    Ok(())
+}
+
+pub(crate) fn readable_size_from(size: u64) -> String {
     #[allow(
         clippy::cast_sign_loss,
         clippy::cast_precision_loss,
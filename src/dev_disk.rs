@@ -1,7 +1,10 @@
 use std::collections::{HashMap, HashSet};
 use std::ffi::OsString;
 use std::fs;
-use std::io::Result;
+
+use super::devnum::DevNum;
+use super::error::{Error, Result, Source};
+use super::paths::SysPaths;
 
 // `/dev/disk/{by-*}` information abstraction for each device and partition
 // the information comes from the filename itself rather than its content
@@ -10,51 +13,70 @@ use std::io::Result;
 #[derive(Debug)]
 pub struct DevDisk {
     pub name: String,              // e.g. "sda" or "sda1"
+    pub devnum: Option<DevNum>,    // from `stat` on the symlink target
     pub label: Option<String>,     // from `/dev/disk/by-label` filename
     pub uuid: Option<Vec<String>>, // from `/dev/disk/by-uuid` filename
 }
 
 // Pack all the information
-#[derive(Debug)]
+// Missing `by-*` directories are not an error, but entries that can't be
+//     read are reported in `diagnostics`
+#[derive(Debug, Default)]
 pub struct DevDiskInfo {
     pub info: Vec<DevDisk>,
+    pub diagnostics: Vec<Error>,
 }
 
 impl DevDiskInfo {
-    pub fn new() -> Result<Self> {
+    pub fn new(paths: &SysPaths) -> Result<Self> {
         let mut labels: HashMap<OsString, String> = HashMap::new();
         let mut uuids: HashMap<OsString, Vec<String>> = HashMap::new();
         let mut device_names = HashSet::new();
+        let mut diagnostics = Vec::new();
 
-        if let Ok(entries) = fs::read_dir("/dev/disk/by-label") {
+        let by_label = paths.dev_disk.join("by-label");
+        if let Ok(entries) = fs::read_dir(&by_label) {
             for entry in entries {
-                let entry = entry?;
-                if let Ok(target) = fs::read_link(entry.path()) {
-                    if let Some(dev_name) = target.file_name() {
-                        labels.insert(
-                            dev_name.to_os_string(),
-                            entry.file_name().to_string_lossy().into_owned(),
-                        );
-                        device_names.insert(dev_name.to_os_string());
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        diagnostics.push(Error::io(Source::DevDisk, &by_label, e));
+                        continue;
                     }
+                };
+                if let Ok(target) = fs::read_link(entry.path())
+                    && let Some(dev_name) = target.file_name()
+                {
+                    labels.insert(
+                        dev_name.to_os_string(),
+                        entry.file_name().to_string_lossy().into_owned(),
+                    );
+                    device_names.insert(dev_name.to_os_string());
                 }
             }
         }
 
-        if let Ok(entries) = fs::read_dir("/dev/disk/by-uuid") {
+        let by_uuid = paths.dev_disk.join("by-uuid");
+        if let Ok(entries) = fs::read_dir(&by_uuid) {
             for entry in entries {
-                let entry = entry?;
-                if let Ok(target) = fs::read_link(entry.path()) {
-                    if let Some(dev_name) = target.file_name() {
-                        let uuid_string = entry.file_name().to_string_lossy().into_owned();
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        diagnostics.push(Error::io(Source::DevDisk, &by_uuid, e));
+                        continue;
+                    }
+                };
+                if let Ok(target) = fs::read_link(entry.path())
+                    && let Some(dev_name) = target.file_name()
+                {
+                    let uuid_string = entry.file_name().to_string_lossy().into_owned();
 
-                        uuids
-                            .entry(dev_name.to_os_string())
-                            .or_default()
-                            .push(uuid_string);
+                    uuids
+                        .entry(dev_name.to_os_string())
+                        .or_default()
+                        .push(uuid_string);
 
-                        device_names.insert(dev_name.to_os_string());
-                    }
+                    device_names.insert(dev_name.to_os_string());
                 }
             }
         }
@@ -63,12 +85,13 @@ impl DevDiskInfo {
         for dev_name in device_names {
             let name = dev_name.to_string_lossy().into_owned();
             info.push(DevDisk {
+                devnum: DevNum::of_block_device(&paths.dev.join(&name)),
                 name,
                 label: labels.remove(&dev_name),
                 uuid: uuids.remove(&dev_name),
             });
         }
 
-        Ok(Self { info })
+        Ok(Self { info, diagnostics })
     }
 }
@@ -0,0 +1,63 @@
+use std::{
+    fmt, fs,
+    os::unix::fs::{FileTypeExt, MetadataExt},
+    path::Path,
+    str::FromStr,
+};
+
+// Kernel device number (`dev_t`), the identity shared by every source
+// e.g. `8:1` for `sda1`, as found in `/sys/block/{device}/dev`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DevNum {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl DevNum {
+    // e.g. `st_rdev` from `stat`
+    pub fn from_dev_t(dev: libc::dev_t) -> Self {
+        // Unsafe safe: only bit manipulation on the given value
+        unsafe {
+            Self {
+                major: libc::major(dev),
+                minor: libc::minor(dev),
+            }
+        }
+    }
+
+    // Device number of a block device node, following symlinks
+    // e.g. `/dev/mapper/cryptroot` or `/dev/disk/by-uuid/{uuid}`
+    pub fn of_block_device(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        metadata
+            .file_type()
+            .is_block_device()
+            .then(|| Self::from_dev_t(metadata.rdev()))
+    }
+
+    // Device number of the filesystem a path lives on (e.g. a mount point)
+    pub fn of_filesystem(path: &Path) -> Option<Self> {
+        fs::metadata(path)
+            .ok()
+            .map(|metadata| Self::from_dev_t(metadata.dev()))
+    }
+}
+
+impl FromStr for DevNum {
+    type Err = ();
+
+    // `MAJOR:MINOR`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (major, minor) = s.trim().split_once(':').ok_or(())?;
+        Ok(Self {
+            major: major.parse().map_err(|_| ())?,
+            minor: minor.parse().map_err(|_| ())?,
+        })
+    }
+}
+
+impl fmt::Display for DevNum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.major, self.minor)
+    }
+}
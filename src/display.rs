@@ -2,6 +2,7 @@ use std::fmt;
 
 use super::dev_disk::DevDiskInfo;
 use super::fstab::FstabInfo;
+use super::nvme::NvmeInfo;
 use super::proc_mounts::ProcMountsInfo;
 use super::sys_block::SysBlockInfo;
 
@@ -38,8 +39,28 @@ impl fmt::Display for SysBlockInfo {
         for device in &self.info {
             writeln!(f)?; // Extra line
             writeln!(f, "⛊ {}", device.name)?;
-            writeln!(f, " • Model: {}", device.info.model)?;
-            writeln!(f, " • Size: {}", readable_size_from(device.info.size))?;
+            writeln!(f, " • Type: {}", device.info.kind)?;
+            if let Some(model) = &device.info.model {
+                writeln!(f, " • Model: {model}")?;
+            }
+            if let Some(dm_info) = &device.info.dm_info {
+                writeln!(f, " • Mapper: {}", dm_info.mapper_path())?;
+            }
+            if let Some(md_info) = &device.info.md_info {
+                let level = md_info.level.as_deref().unwrap_or("unknown level");
+                writeln!(f, " • RAID: {level}, {}", md_info.health())?;
+            }
+            if let Some(bcache) = &device.info.bcache {
+                writeln!(f, " • bcache: {}", bcache.role)?;
+            }
+            if let Some(zoned) = &device.info.zoned {
+                writeln!(f, " • Zoned: {}, {} zones", zoned.model, zoned.nr_zones)?;
+            }
+            if device.info.medium.present {
+                writeln!(f, " • Size: {}", readable_size_from(device.info.size))?;
+            } else {
+                writeln!(f, " • Medium: None")?;
+            }
             writeln!(
                 f,
                 " • Removable: {}",
@@ -133,3 +154,42 @@ impl fmt::Display for FstabInfo {
         Ok(())
     }
 }
+
+impl fmt::Display for NvmeInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f)?; // Extra line
+        writeln!(f, "from `/sys/class/nvme`")?;
+        writeln!(f, "======================")?;
+
+        for controller in &self.info {
+            writeln!(f)?; // Extra line
+            writeln!(f, "⛊ {}", controller.name)?;
+            let fields = [
+                ("Model", controller.model.as_ref()),
+                ("Serial", controller.serial.as_ref()),
+                ("Firmware", controller.firmware_rev.as_ref()),
+                ("Transport", controller.transport.as_ref()),
+                ("Subsystem NQN", controller.subsysnqn.as_ref()),
+                ("State", controller.state.as_ref()),
+            ];
+            for (name, value) in fields {
+                if let Some(value) = value {
+                    writeln!(f, " • {name}: {value}")?;
+                }
+            }
+            if let Some(cntlid) = controller.cntlid {
+                writeln!(f, " • Controller ID: {cntlid}")?;
+            }
+
+            // Namespaces grouped under their controller
+            if !controller.namespaces.is_empty() {
+                writeln!(f, " • Namespaces:")?;
+                for namespace in &controller.namespaces {
+                    writeln!(f, "    ⛉ {namespace}")?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
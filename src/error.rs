@@ -0,0 +1,118 @@
+use std::{fmt, io, path::PathBuf};
+
+// The source an error comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    SysBlock,   // `/sys/block`
+    DevDisk,    // `/dev/disk`
+    ProcMounts, // `/proc/mounts`
+    Fstab,      // `/etc/fstab`
+    Magic,      // filesystem signature probing
+    Nvme,       // `/sys/class/nvme`
+}
+
+// Everything that can go wrong while collecting information
+// Each variant records the source, the device (if any) and the file involved
+#[derive(Debug)]
+pub enum Error {
+    // A file or directory could not be read
+    Io {
+        source: Source,
+        device: Option<String>,
+        path: PathBuf,
+        error: io::Error,
+    },
+    // A file was read, but its content could not be understood
+    Parse {
+        source: Source,
+        device: Option<String>,
+        path: PathBuf,
+        value: String,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn io(source: Source, path: impl Into<PathBuf>, error: io::Error) -> Self {
+        Self::Io {
+            source,
+            device: None,
+            path: path.into(),
+            error,
+        }
+    }
+
+    pub fn parse(source: Source, path: impl Into<PathBuf>, value: impl Into<String>) -> Self {
+        Self::Parse {
+            source,
+            device: None,
+            path: path.into(),
+            value: value.into(),
+        }
+    }
+
+    // Attach the device the error belongs to
+    #[must_use]
+    pub fn with_device(mut self, name: &str) -> Self {
+        match &mut self {
+            Self::Io { device, .. } | Self::Parse { device, .. } => {
+                *device = Some(name.to_string());
+            }
+        }
+        self
+    }
+
+    // Not named `source` to avoid clashing with `std::error::Error::source`
+    pub fn origin(&self) -> Source {
+        match self {
+            Self::Io { source, .. } | Self::Parse { source, .. } => *source,
+        }
+    }
+
+    pub fn device(&self) -> Option<&str> {
+        match self {
+            Self::Io { device, .. } | Self::Parse { device, .. } => device.as_deref(),
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::SysBlock => "/sys/block",
+            Self::DevDisk => "/dev/disk",
+            Self::ProcMounts => "/proc/mounts",
+            Self::Fstab => "/etc/fstab",
+            Self::Magic => "magic",
+            Self::Nvme => "/sys/class/nvme",
+        };
+        f.write_str(name)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] ", self.origin())?;
+        if let Some(device) = self.device() {
+            write!(f, "{device}: ")?;
+        }
+        match self {
+            Self::Io { path, error, .. } => {
+                write!(f, "failed to read `{}`: {error}", path.display())
+            }
+            Self::Parse { path, value, .. } => {
+                write!(f, "unexpected content in `{}`: {value:?}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { error, .. } => Some(error),
+            Self::Parse { .. } => None,
+        }
+    }
+}
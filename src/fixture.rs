@@ -0,0 +1,51 @@
+use std::{fs, os::unix::fs::symlink, path::PathBuf, process};
+
+use super::paths::SysPaths;
+
+// Throwaway system root for tests, read through `SysPaths::with_root`
+// Each test picks its own name so they can run in parallel, the whole tree
+//     is removed when dropped
+pub struct Fixture {
+    pub root: PathBuf,
+}
+
+impl Fixture {
+    pub fn new(name: &str) -> Self {
+        let root = std::env::temp_dir().join(format!("rutiles-{name}-{}", process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).expect("create fixture root");
+        Self { root }
+    }
+
+    // e.g. `file("sys/block/sda/size", "2048")`, parents are created
+    pub fn file(&self, path: &str, content: impl AsRef<[u8]>) -> &Self {
+        let path = self.parent_of(path);
+        fs::write(&path, content).expect("write fixture file");
+        self
+    }
+
+    // `target` is taken as is, relative targets resolve from the link's directory
+    pub fn symlink(&self, path: &str, target: &str) -> &Self {
+        let path = self.parent_of(path);
+        symlink(target, &path).expect("create fixture symlink");
+        self
+    }
+
+    pub fn paths(&self) -> SysPaths {
+        SysPaths::with_root(&self.root)
+    }
+
+    fn parent_of(&self, path: &str) -> PathBuf {
+        let path = self.root.join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).expect("create fixture directory");
+        }
+        path
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
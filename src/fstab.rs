@@ -2,11 +2,17 @@ use libc::{FILE, c_char, c_int, endmntent, getmntent_r, mntent, setmntent};
 
 use std::{
     ffi::{CStr, CString},
-    io::{Error, ErrorKind, Result},
+    io::{self, ErrorKind},
+    os::unix::ffi::OsStrExt,
+    path::Path,
     ptr,
 };
 use tracing::debug;
 
+use super::devnum::DevNum;
+use super::error::{Error, Result, Source};
+use super::paths::SysPaths;
+
 // A single entry from `/etc/fstab`, based on the `mntent` struct:
 // From `getmntent` man page:
 //     > The mntent structure is defined in <mntent.h> as follows:
@@ -18,6 +24,8 @@ use tracing::debug;
 //     >          int   mnt_freq;     /* dump frequency in days */
 //     >          int   mnt_passno;   /* pass number on parallel fsck */
 //     >      };
+// `devnum` is only known when the device is a path (e.g. `/dev/sda1`),
+//     not a tag such as `UUID=...`
 #[derive(Debug, Clone)]
 pub struct Fstab {
     pub device: String,
@@ -26,6 +34,7 @@ pub struct Fstab {
     pub options: Vec<String>,
     pub dump_freq: i32,
     pub fsck_pass: i32,
+    pub devnum: Option<DevNum>,
 }
 
 impl Fstab {
@@ -47,12 +56,13 @@ impl Fstab {
             options,
             dump_freq: ent.mnt_freq,
             fsck_pass: ent.mnt_passno,
+            devnum: None,
         }
     }
 }
 
 // Pack `/etc/fstab` information
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct FstabInfo {
     pub info: Vec<Fstab>,
 }
@@ -60,8 +70,9 @@ pub struct FstabInfo {
 // From the `fstab` man page:
 // > The proper way to read records from fstab is to use the routines getmntent(3) or libmount
 impl FstabInfo {
-    pub fn new() -> Result<Self> {
-        let file = FileHandle::new("/etc/fstab", "r")?;
+    pub fn new(paths: &SysPaths) -> Result<Self> {
+        let file = FileHandle::new(&paths.fstab, "r")
+            .map_err(|e| Error::io(Source::Fstab, &paths.fstab, e))?;
         let mut info = Vec::new();
 
         // getmntent_r requires a buffer
@@ -80,7 +91,11 @@ impl FstabInfo {
         while !unsafe { getmntent_r(file.0, &mut ent, buf.as_mut_ptr(), buf_size as c_int) }
             .is_null()
         {
-            let entry = unsafe { Fstab::from_mntent(&ent) };
+            let mut entry = unsafe { Fstab::from_mntent(&ent) };
+            entry.devnum = entry
+                .device
+                .strip_prefix("/dev/")
+                .and_then(|name| DevNum::of_block_device(&paths.dev.join(name)));
             debug!("Successfully parsed `fstab` entry for {}", entry.device);
             info.push(entry);
         }
@@ -93,13 +108,14 @@ impl FstabInfo {
 struct FileHandle(*mut FILE);
 
 impl FileHandle {
-    fn new(path: &str, mode: &str) -> Result<Self> {
-        let path = CString::new(path).map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
-        let mode = CString::new(mode).map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+    fn new(path: &Path, mode: &str) -> io::Result<Self> {
+        let path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|e| io::Error::new(ErrorKind::InvalidInput, e))?;
+        let mode = CString::new(mode).map_err(|e| io::Error::new(ErrorKind::InvalidInput, e))?;
 
         let file_ptr = unsafe { setmntent(path.as_ptr(), mode.as_ptr()) };
         if file_ptr.is_null() {
-            return Err(Error::last_os_error());
+            return Err(io::Error::last_os_error());
         }
 
         debug!("Successfully opened `{}`", path.to_string_lossy());
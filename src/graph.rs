@@ -0,0 +1,530 @@
+use std::collections::HashMap;
+use tracing::warn;
+
+use super::dev_disk::{DevDisk, DevDiskInfo};
+use super::devnum::DevNum;
+use super::fstab::{Fstab, FstabInfo};
+use super::magic::get_fstype_with_magic;
+use super::paths::SysPaths;
+use super::proc_mounts::{ProcMounts, ProcMountsInfo};
+use super::sys_block::{
+    BcacheInfo, BcacheRole, DeviceKind, DmInfo, HardwareIdentity, LoopInfo, MdInfo, MediumState,
+    NvmeNamespace, PartitionGeometry, QueueInfo, ScsiInfo, SysBlockInfo, ZonedInfo,
+};
+
+// Index of a node in `StorageGraph::nodes`
+pub type NodeId = usize;
+
+// Everything we know about a node, merged from every source
+#[derive(Debug, Clone, Default)]
+pub struct NodeInfo {
+    pub devnum: Option<DevNum>,
+    pub diskseq: Option<u64>,
+    pub partname: Option<String>,
+    pub model: Option<String>,
+    pub identity: Option<HardwareIdentity>,
+    pub scsi: Option<ScsiInfo>,
+    pub size: Option<u64>,
+    pub filesystem: Option<String>,
+    pub label: Option<String>,
+    pub mount_point: Option<String>,
+    pub removable: Option<bool>,
+    pub medium: Option<MediumState>,
+    pub uuids: Option<Vec<String>>,
+    pub fstab_entry: Option<Fstab>,
+    pub loop_info: Option<LoopInfo>,
+    pub dm_info: Option<DmInfo>,
+    pub md_info: Option<MdInfo>,
+    pub bcache: Option<BcacheInfo>,
+    pub nvme: Option<NvmeNamespace>,
+    pub zoned: Option<ZonedInfo>,
+    pub queue: Option<QueueInfo>,
+    pub geometry: Option<PartitionGeometry>,
+    pub holders: Vec<String>,
+    pub slaves: Vec<String>,
+}
+
+// A single block device, at any depth of the stack
+// `parents` are the nodes it's built on (e.g. a partition's disk, or every
+//     member of a RAID array) and `children` the nodes built on top of it
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub name: String,
+    pub kind: DeviceKind,
+    pub info: NodeInfo,
+    pub parents: Vec<NodeId>,
+    pub children: Vec<NodeId>,
+}
+
+// Arbitrary-depth model of the storage stack
+// Nodes are sorted by name and can be shared by several parents
+#[derive(Debug, Default)]
+pub struct StorageGraph {
+    pub nodes: Vec<Node>,
+    names: HashMap<String, NodeId>,   // kernel name, e.g. `sda1`
+    devnums: HashMap<DevNum, NodeId>, // major:minor, e.g. `8:1`
+}
+
+// Every other source indexed once, so merging a node doesn't scan them
+// When an identifier shows up more than once, the first entry wins
+struct SourceIndex<'a> {
+    dev_disk: JoinIndex<'a, DevDisk>,
+    proc_mounts: JoinIndex<'a, ProcMounts>,
+    fstab: JoinIndex<'a, Fstab>,
+    fstab_tags: HashMap<&'a str, &'a Fstab>, // by `UUID=...`, `LABEL=...`, etc.
+}
+
+// Entries are joined on their device number
+// The name is only a fallback for entries whose number couldn't be resolved
+//     (e.g. captured fixtures without device nodes)
+struct JoinIndex<'a, T> {
+    by_devnum: HashMap<DevNum, &'a T>,
+    by_name: HashMap<&'a str, &'a T>,
+}
+
+impl<'a, T> JoinIndex<'a, T> {
+    fn new(entries: &'a [T], key: impl Fn(&'a T) -> (Option<DevNum>, &'a str)) -> Self {
+        let mut index = Self {
+            by_devnum: HashMap::with_capacity(entries.len()),
+            by_name: HashMap::new(),
+        };
+        for entry in entries {
+            match key(entry) {
+                (Some(devnum), _) => index.by_devnum.entry(devnum).or_insert(entry),
+                (None, name) => index.by_name.entry(name).or_insert(entry),
+            };
+        }
+        index
+    }
+
+    fn get(&self, devnum: Option<DevNum>, name: &str) -> Option<&'a T> {
+        devnum
+            .and_then(|devnum| self.by_devnum.get(&devnum))
+            .or_else(|| self.by_name.get(name))
+            .copied()
+    }
+}
+
+impl<'a> SourceIndex<'a> {
+    fn new(
+        dev_disk: &'a DevDiskInfo,
+        proc_mounts: &'a ProcMountsInfo,
+        fstab: &'a FstabInfo,
+    ) -> Self {
+        let mut fstab_tags = HashMap::with_capacity(fstab.info.len());
+        for entry in &fstab.info {
+            fstab_tags.entry(entry.device.as_str()).or_insert(entry);
+        }
+
+        Self {
+            dev_disk: JoinIndex::new(&dev_disk.info, |e| (e.devnum, e.name.as_str())),
+            proc_mounts: JoinIndex::new(&proc_mounts.info, |e| (e.devnum, e.name.as_str())),
+            fstab: JoinIndex::new(&fstab.info, |e| {
+                (e.devnum, e.device.strip_prefix("/dev/").unwrap_or(""))
+            }),
+            fstab_tags,
+        }
+    }
+}
+
+impl StorageGraph {
+    pub fn new(
+        paths: &SysPaths,
+        sys_block: &SysBlockInfo,
+        dev_disk: &DevDiskInfo,
+        proc_mounts: &ProcMountsInfo,
+        fstab: &FstabInfo,
+    ) -> Self {
+        let mut graph = Self::default();
+
+        // Start with the information from `/sys/block`
+        for device in &sys_block.info {
+            graph.add_node(
+                &device.name,
+                device.info.kind,
+                NodeInfo {
+                    devnum: device.info.devnum,
+                    diskseq: device.info.diskseq,
+                    model: device.info.model.clone(),
+                    identity: device.info.identity.clone(),
+                    scsi: device.info.scsi.clone(),
+                    size: Some(device.info.size),
+                    removable: Some(device.info.removable),
+                    medium: Some(device.info.medium.clone()),
+                    loop_info: device.info.loop_info.clone(),
+                    dm_info: device.info.dm_info.clone(),
+                    md_info: device.info.md_info.clone(),
+                    bcache: device.info.bcache.clone(),
+                    nvme: device.info.nvme.clone(),
+                    zoned: device.info.zoned,
+                    queue: device.info.queue.clone(),
+                    holders: device.info.holders.clone(),
+                    slaves: device.info.slaves.clone(),
+                    ..NodeInfo::default()
+                },
+            );
+            for part in device.part.iter().flatten() {
+                graph.add_node(
+                    &part.name,
+                    DeviceKind::Partition,
+                    NodeInfo {
+                        devnum: part.info.devnum,
+                        partname: part.info.partname.clone(),
+                        size: Some(part.info.size),
+                        removable: Some(part.info.removable),
+                        // Propagated from the device, so partitions of an empty
+                        //     or offline drive aren't probed either
+                        medium: Some(device.info.medium.clone()),
+                        queue: part.info.queue.clone(),
+                        bcache: part.info.bcache.clone(),
+                        geometry: Some(part.info.geometry),
+                        holders: part.info.holders.clone(),
+                        slaves: part.info.slaves.clone(),
+                        ..NodeInfo::default()
+                    },
+                );
+            }
+        }
+        graph.nodes.sort_by(|a, b| a.name.cmp(&b.name));
+        graph.build_indexes();
+
+        // Link partitions to their device, now that indexes are stable
+        for device in &sys_block.info {
+            for part in device.part.iter().flatten() {
+                if let (Some(parent), Some(child)) =
+                    (graph.find(&device.name), graph.find(&part.name))
+                {
+                    graph.add_edge(parent, child);
+                }
+            }
+        }
+
+        // Stacked devices, from both sides since either may be missing
+        //     (e.g. `sda2` held by `dm-0`, `md0` built from `sdb1` and `sdc1`)
+        for id in 0..graph.nodes.len() {
+            let node = &graph.nodes[id];
+            let holders: Vec<NodeId> = node
+                .info
+                .holders
+                .iter()
+                .filter_map(|h| graph.find(h))
+                .collect();
+            let slaves: Vec<NodeId> = node
+                .info
+                .slaves
+                .iter()
+                .filter_map(|s| graph.find(s))
+                .collect();
+            for holder in holders {
+                graph.add_edge(id, holder);
+            }
+            for slave in slaves {
+                graph.add_edge(slave, id);
+            }
+        }
+
+        // bcache devices are also built on their cache device, which isn't
+        //     one of their slaves
+        // Seen from the members: a backing device only lists its own bcache
+        //     device, a cache device lists every bcache device of its set
+        for id in 0..graph.nodes.len() {
+            let Some(bcache) = &graph.nodes[id].info.bcache else {
+                continue;
+            };
+            if bcache.role == BcacheRole::Device {
+                continue;
+            }
+            let devices: Vec<NodeId> = bcache
+                .devices
+                .iter()
+                .filter_map(|d| graph.find(d))
+                .collect();
+            for device in devices {
+                graph.add_edge(id, device);
+            }
+        }
+
+        // Then merge the other sources into every node, whatever its depth
+        let index = SourceIndex::new(dev_disk, proc_mounts, fstab);
+        for node in &mut graph.nodes {
+            merge_sources(paths, node, &index);
+        }
+
+        graph
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id]
+    }
+
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.names.get(name).copied()
+    }
+
+    pub fn find_devnum(&self, devnum: DevNum) -> Option<NodeId> {
+        self.devnums.get(&devnum).copied()
+    }
+
+    // Nodes that aren't built on anything else (e.g. whole disks)
+    pub fn roots(&self) -> impl Iterator<Item = NodeId> + '_ {
+        (0..self.nodes.len()).filter(|&id| self.nodes[id].parents.is_empty())
+    }
+
+    // Must be called again whenever nodes move
+    fn build_indexes(&mut self) {
+        self.names.clear();
+        self.devnums.clear();
+        for (id, node) in self.nodes.iter().enumerate() {
+            self.names.insert(node.name.clone(), id);
+            if let Some(devnum) = node.info.devnum {
+                self.devnums.insert(devnum, id);
+            }
+        }
+    }
+
+    fn add_node(&mut self, name: &str, kind: DeviceKind, info: NodeInfo) {
+        self.nodes.push(Node {
+            name: name.to_string(),
+            kind,
+            info,
+            parents: Vec::new(),
+            children: Vec::new(),
+        });
+    }
+
+    // Children are kept sorted, since nodes are
+    fn add_edge(&mut self, parent: NodeId, child: NodeId) {
+        if !self.nodes[parent].children.contains(&child) {
+            self.nodes[parent].children.push(child);
+            self.nodes[parent].children.sort_unstable();
+        }
+        if !self.nodes[child].parents.contains(&parent) {
+            self.nodes[child].parents.push(parent);
+            self.nodes[child].parents.sort_unstable();
+        }
+    }
+}
+
+fn merge_sources(paths: &SysPaths, node: &mut Node, index: &SourceIndex<'_>) {
+    let info = &mut node.info;
+    let (devnum, name) = (info.devnum, node.name.as_str());
+
+    // Add information from `/dev/disk`
+    if let Some(dev_disk) = index.dev_disk.get(devnum, name) {
+        info.label.clone_from(&dev_disk.label);
+        info.uuids.clone_from(&dev_disk.uuid);
+    }
+
+    // Add information from `/proc/mounts`
+    if let Some(proc_mounts) = index.proc_mounts.get(devnum, name) {
+        info.mount_point = Some(proc_mounts.mount_point.clone());
+        info.filesystem = Some(proc_mounts.fstype.clone());
+    }
+
+    // Fallback to magic numbers to find filesystem type
+    // Only for the top of the stack, since anything below holds something else,
+    //     and never for empty or offline drives
+    let can_probe = info.medium.as_ref().is_none_or(MediumState::can_probe);
+    if info.filesystem.is_none() && node.children.is_empty() && can_probe {
+        info.filesystem = match get_fstype_with_magic(paths, name) {
+            Ok(fs_type) => fs_type,
+            Err(e) => {
+                warn!("Failed to get fstype from signature: {e}");
+                None
+            }
+        }
+    }
+
+    // Try to find a matching fstab entry
+    info.fstab_entry = index
+        .fstab
+        .get(devnum, name)
+        .or_else(|| find_fstab_entry(index, info))
+        .cloned();
+}
+
+// By the first UUID, then by label, e.g. `UUID=1234-ABCD` or `LABEL=root`
+fn find_fstab_entry<'a>(index: &SourceIndex<'a>, info: &NodeInfo) -> Option<&'a Fstab> {
+    let by_uuid = info
+        .uuids
+        .as_ref()
+        .and_then(|uuids| uuids.first())
+        .map(|uuid| format!("UUID={uuid}"));
+    let by_label = info.label.as_ref().map(|label| format!("LABEL={label}"));
+
+    [by_uuid, by_label]
+        .into_iter()
+        .flatten()
+        .find_map(|key| index.fstab_tags.get(key.as_str()).copied())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+
+    fn parents(graph: &StorageGraph, name: &str) -> Vec<String> {
+        let node = &graph.nodes[graph.find(name).unwrap()];
+        let mut names: Vec<String> = node
+            .parents
+            .iter()
+            .map(|&id| graph.nodes[id].name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    // One SSD caching two disks, each disk only backs its own bcache device
+    #[test]
+    fn links_bcache_devices_to_their_own_backing_device() {
+        let fixture = Fixture::new("graph-bcache");
+        for (name, dev) in [
+            ("sdb", "8:16"),
+            ("sdc", "8:32"),
+            ("nvme0n1", "259:0"),
+            ("bcache0", "252:0"),
+            ("bcache1", "252:128"),
+        ] {
+            fixture
+                .file(&format!("sys/block/{name}/size"), "2048\n")
+                .file(&format!("sys/block/{name}/removable"), "0\n")
+                .file(&format!("sys/block/{name}/dev"), dev);
+        }
+        for (backing, device, bdev) in [("sdb", "bcache0", "bdev0"), ("sdc", "bcache1", "bdev1")] {
+            fixture
+                .file(&format!("sys/block/{backing}/bcache/state"), "clean\n")
+                .symlink(
+                    &format!("sys/block/{backing}/bcache/dev"),
+                    &format!("../../{device}"),
+                )
+                .symlink(
+                    &format!("sys/block/{backing}/bcache/cache"),
+                    "../../../fs/bcache/f5a1",
+                )
+                .symlink(
+                    &format!("sys/fs/bcache/f5a1/{bdev}"),
+                    &format!("../../../block/{backing}/bcache"),
+                )
+                .symlink(
+                    &format!("sys/block/{device}/bcache"),
+                    &format!("../{backing}/bcache"),
+                );
+        }
+        fixture
+            .symlink("sys/block/nvme0n1/bcache/set", "../../../fs/bcache/f5a1")
+            .symlink("sys/fs/bcache/f5a1/cache0", "../../../block/nvme0n1/bcache");
+
+        let paths = fixture.paths();
+        let sys_block = SysBlockInfo::new(&paths).unwrap();
+        assert!(
+            sys_block.diagnostics.is_empty(),
+            "{:?}",
+            sys_block.diagnostics
+        );
+        let graph = StorageGraph::new(
+            &paths,
+            &sys_block,
+            &DevDiskInfo::default(),
+            &ProcMountsInfo::default(),
+            &FstabInfo::default(),
+        );
+
+        assert_eq!(parents(&graph, "bcache0"), ["nvme0n1", "sdb"]);
+        assert_eq!(parents(&graph, "bcache1"), ["nvme0n1", "sdc"]);
+        let cache = graph.nodes[graph.find("nvme0n1").unwrap()]
+            .info
+            .bcache
+            .as_ref()
+            .unwrap();
+        assert_eq!(cache.role, BcacheRole::Cache);
+        assert_eq!(cache.devices, ["bcache0", "bcache1"]);
+    }
+
+    fn mount(name: &str, mount_point: &str, devnum: Option<DevNum>) -> ProcMounts {
+        ProcMounts {
+            name: name.to_string(),
+            mount_point: mount_point.to_string(),
+            fstype: "ext4".to_string(),
+            devnum,
+        }
+    }
+
+    fn fstab(device: &str, mount_point: &str, devnum: Option<DevNum>) -> Fstab {
+        Fstab {
+            device: device.to_string(),
+            mount_point: mount_point.to_string(),
+            fs_type: "ext4".to_string(),
+            options: vec!["defaults".to_string()],
+            dump_freq: 0,
+            fsck_pass: 1,
+            devnum,
+        }
+    }
+
+    // Sources rarely name devices like the kernel does, e.g. `/dev/mapper/cryptroot`
+    //     for `dm-0` or `/dev/root` for whatever holds `/`
+    #[test]
+    fn joins_sources_on_the_device_number() {
+        let fixture = Fixture::new("graph-join");
+        fixture
+            .file("sys/block/sda/size", "2048\n")
+            .file("sys/block/sda/removable", "0\n")
+            .file("sys/block/sda/dev", "8:0\n")
+            .file("sys/block/sda/sda2/size", "1024\n")
+            .file("sys/block/sda/sda2/dev", "8:2\n")
+            .file("sys/block/sda/sda2/uevent", "DEVTYPE=partition\nPARTN=2\n")
+            .file("sys/block/dm-0/size", "1024\n")
+            .file("sys/block/dm-0/removable", "0\n")
+            .file("sys/block/dm-0/dev", "253:0\n")
+            .file("sys/block/dm-0/dm/name", "cryptroot\n");
+        let dm0 = Some(DevNum {
+            major: 253,
+            minor: 0,
+        });
+        let sda2 = Some(DevNum { major: 8, minor: 2 });
+
+        let dev_disk = DevDiskInfo {
+            info: vec![DevDisk {
+                name: "disk/by-uuid/2222-3333".to_string(),
+                devnum: sda2,
+                label: Some("boot".to_string()),
+                uuid: Some(vec!["2222-3333".to_string()]),
+            }],
+            diagnostics: Vec::new(),
+        };
+        // Entries named after the kernel device, but for another device number,
+        //     must lose to the ones that match it
+        let proc_mounts = ProcMountsInfo {
+            info: vec![
+                mount("dm-0", "/stale", Some(DevNum { major: 8, minor: 9 })),
+                mount("sda2", "/stale", None),
+                mount("root", "/", dm0),
+                mount("disk/by-uuid/2222-3333", "/boot", sda2),
+            ],
+        };
+        let fstab = FstabInfo {
+            info: vec![
+                fstab("/dev/sda2", "/stale", None),
+                fstab("/dev/mapper/cryptroot", "/", dm0),
+                fstab("/dev/disk/by-uuid/2222-3333", "/boot", sda2),
+            ],
+        };
+
+        let paths = fixture.paths();
+        let sys_block = SysBlockInfo::new(&paths).unwrap();
+        let graph = StorageGraph::new(&paths, &sys_block, &dev_disk, &proc_mounts, &fstab);
+
+        let info = |name: &str| &graph.nodes[graph.find(name).unwrap()].info;
+        assert_eq!(info("dm-0").mount_point.as_deref(), Some("/"));
+        assert_eq!(
+            info("dm-0").fstab_entry.as_ref().map(|e| e.device.as_str()),
+            Some("/dev/mapper/cryptroot")
+        );
+        assert_eq!(info("sda2").label.as_deref(), Some("boot"));
+        assert_eq!(info("sda2").mount_point.as_deref(), Some("/boot"));
+        assert_eq!(
+            info("sda2").fstab_entry.as_ref().map(|e| e.device.as_str()),
+            Some("/dev/disk/by-uuid/2222-3333")
+        );
+    }
+}
@@ -0,0 +1,323 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::error::{Error, Result, Source};
+use super::paths::SysPaths;
+use super::sys_block::{SECTOR_SIZE, SysBlockInfo};
+
+// `/sys/block/{device}/stat` and `/sys/block/{device}/{partition}/stat`
+// Every counter only grows since boot, so rates come from the difference
+//     between two samples, see `IoSample::delta_since`
+// Fields were added over time, missing ones are 0
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IoStat {
+    pub read_ios: u64,
+    pub read_merges: u64,
+    pub read_sectors: u64,
+    pub read_ticks: u64, // ms spent on reads
+    pub write_ios: u64,
+    pub write_merges: u64,
+    pub write_sectors: u64,
+    pub write_ticks: u64,   // ms spent on writes
+    pub in_flight: u64,     // requests currently in flight, not a counter
+    pub io_ticks: u64,      // ms with at least one request in flight
+    pub time_in_queue: u64, // ms spent by every request, weighted by their number
+    pub discard_ios: u64,
+    pub discard_merges: u64,
+    pub discard_sectors: u64,
+    pub discard_ticks: u64,
+    pub flush_ios: u64,
+    pub flush_ticks: u64,
+}
+
+// Counters of every device and partition at a given time
+#[derive(Debug)]
+pub struct IoSample {
+    pub taken: Instant,
+    pub stats: Vec<(String, IoStat)>, // by kernel name, devices followed by their partitions
+    pub diagnostics: Vec<Error>,      // devices that couldn't be read (e.g. removed meanwhile)
+}
+
+// Activity between two samples, as shown by `iostat -x`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IoDelta {
+    pub name: String,
+    pub read_iops: f64,
+    pub write_iops: f64,
+    pub read_bytes_per_sec: f64,
+    pub write_bytes_per_sec: f64,
+    pub read_latency_ms: f64,  // average time per read, `r_await`
+    pub write_latency_ms: f64, // average time per write, `w_await`
+    pub queue_depth: f64,      // average number of requests in flight, `aqu-sz`
+    pub utilization: f64,      // percentage of time busy, `%util`
+}
+
+impl IoStat {
+    pub fn read(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).map_err(|e| Error::io(Source::SysBlock, path, e))?;
+        let mut fields = content.split_whitespace().map(str::parse::<u64>);
+        let mut next = || -> Result<u64> {
+            match fields.next() {
+                Some(Ok(value)) => Ok(value),
+                Some(Err(_)) => Err(Error::parse(Source::SysBlock, path, content.trim())),
+                None => Ok(0),
+            }
+        };
+
+        Ok(Self {
+            read_ios: next()?,
+            read_merges: next()?,
+            read_sectors: next()?,
+            read_ticks: next()?,
+            write_ios: next()?,
+            write_merges: next()?,
+            write_sectors: next()?,
+            write_ticks: next()?,
+            in_flight: next()?,
+            io_ticks: next()?,
+            time_in_queue: next()?,
+            discard_ios: next()?,
+            discard_merges: next()?,
+            discard_sectors: next()?,
+            discard_ticks: next()?,
+            flush_ios: next()?,
+            flush_ticks: next()?,
+        })
+    }
+}
+
+impl IoSample {
+    // Reads the counters of every device and partition known to `sys_block`
+    pub fn new(paths: &SysPaths, sys_block: &SysBlockInfo) -> Self {
+        let mut stats = Vec::new();
+        let mut diagnostics = Vec::new();
+        let mut read = |name: &str, path: PathBuf| match IoStat::read(&path) {
+            Ok(stat) => stats.push((name.to_string(), stat)),
+            Err(e) => diagnostics.push(e.with_device(name)),
+        };
+
+        // Sorted by name, like the combined view
+        let mut devices: Vec<_> = sys_block.info.iter().collect();
+        devices.sort_by(|a, b| a.name.cmp(&b.name));
+        for device in devices {
+            let device_path = paths.sys_block.join(&device.name);
+            read(&device.name, device_path.join("stat"));
+            for part in device.part.iter().flatten() {
+                read(&part.name, device_path.join(&part.name).join("stat"));
+            }
+        }
+
+        Self {
+            taken: Instant::now(),
+            stats,
+            diagnostics,
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&IoStat> {
+        self.stats
+            .iter()
+            .find_map(|(stat_name, stat)| (stat_name == name).then_some(stat))
+    }
+
+    // Rates between an earlier sample and this one
+    // Devices missing from either sample are left out
+    // The earlier sample is indexed once, hosts can have hundreds of devices
+    pub fn delta_since(&self, earlier: &Self) -> Vec<IoDelta> {
+        let elapsed = self.taken.saturating_duration_since(earlier.taken);
+        let earlier: HashMap<&str, &IoStat> = earlier
+            .stats
+            .iter()
+            .map(|(name, stat)| (name.as_str(), stat))
+            .collect();
+        self.stats
+            .iter()
+            .filter_map(|(name, after)| {
+                let before = earlier.get(name.as_str())?;
+                Some(IoDelta::new(name, before, after, elapsed))
+            })
+            .collect()
+    }
+}
+
+impl IoDelta {
+    #[allow(clippy::cast_precision_loss)]
+    pub fn new(name: &str, before: &IoStat, after: &IoStat, elapsed: Duration) -> Self {
+        let secs = elapsed.as_secs_f64();
+        if secs <= 0.0 {
+            return Self {
+                name: name.to_string(),
+                ..Self::default()
+            };
+        }
+
+        // Counters restart from 0 when a device is re-created
+        let diff = |field: fn(&IoStat) -> u64| field(after).saturating_sub(field(before)) as f64;
+        let per_sec = |value: f64| value / secs;
+        let average = |ticks: f64, ios: f64| if ios > 0.0 { ticks / ios } else { 0.0 };
+        let (read_ios, write_ios) = (diff(|s| s.read_ios), diff(|s| s.write_ios));
+        let sector_size = SECTOR_SIZE as f64;
+
+        Self {
+            name: name.to_string(),
+            read_iops: per_sec(read_ios),
+            write_iops: per_sec(write_ios),
+            read_bytes_per_sec: per_sec(diff(|s| s.read_sectors) * sector_size),
+            write_bytes_per_sec: per_sec(diff(|s| s.write_sectors) * sector_size),
+            read_latency_ms: average(diff(|s| s.read_ticks), read_ios),
+            write_latency_ms: average(diff(|s| s.write_ticks), write_ios),
+            queue_depth: per_sec(diff(|s| s.time_in_queue)) / 1000.0,
+            utilization: (per_sec(diff(|s| s.io_ticks)) / 10.0).min(100.0),
+        }
+    }
+}
+
+// Takes two samples `interval` apart and returns their delta, for callers
+//     that just want the current activity
+pub fn sample_delta(
+    paths: &SysPaths,
+    sys_block: &SysBlockInfo,
+    interval: Duration,
+) -> (Vec<IoDelta>, Vec<Error>) {
+    let before = IoSample::new(paths, sys_block);
+    thread::sleep(interval);
+    let after = IoSample::new(paths, sys_block);
+
+    let deltas = after.delta_since(&before);
+    let mut diagnostics = before.diagnostics;
+    diagnostics.extend(after.diagnostics);
+    (deltas, diagnostics)
+}
+
+// Aligned table, like `iostat -x` with fewer columns
+pub fn render(deltas: &[IoDelta]) -> String {
+    let width = deltas
+        .iter()
+        .map(|delta| delta.name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("DEVICE".len());
+
+    let mut out = format!(
+        "{:<width$} {:>9} {:>9} {:>10} {:>10} {:>8} {:>8} {:>7} {:>6}\n",
+        "DEVICE", "R/S", "W/S", "RKB/S", "WKB/S", "R-AWAIT", "W-AWAIT", "AQU-SZ", "%UTIL"
+    );
+    for delta in deltas {
+        out.push_str(&format!(
+            "{:<width$} {:>9.2} {:>9.2} {:>10.2} {:>10.2} {:>8.2} {:>8.2} {:>7.2} {:>6.2}\n",
+            delta.name,
+            delta.read_iops,
+            delta.write_iops,
+            delta.read_bytes_per_sec / 1024.0,
+            delta.write_bytes_per_sec / 1024.0,
+            delta.read_latency_ms,
+            delta.write_latency_ms,
+            delta.queue_depth,
+            delta.utilization,
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TWO_SECONDS: Duration = Duration::from_secs(2);
+
+    #[test]
+    fn computes_rates_latencies_and_utilization() {
+        let before = IoStat {
+            read_ios: 1000,
+            read_sectors: 8000,
+            io_ticks: 500,
+            ..IoStat::default()
+        };
+        let after = IoStat {
+            read_ios: 1200,
+            read_sectors: 12096,
+            read_ticks: 400,
+            write_ios: 100,
+            write_sectors: 2048,
+            write_ticks: 50,
+            io_ticks: 1500,
+            time_in_queue: 3000,
+            ..IoStat::default()
+        };
+
+        let delta = IoDelta::new("sda", &before, &after, TWO_SECONDS);
+        assert_eq!(
+            delta,
+            IoDelta {
+                name: "sda".to_string(),
+                read_iops: 100.0,
+                write_iops: 50.0,
+                read_bytes_per_sec: 1024.0 * 1024.0,
+                write_bytes_per_sec: 512.0 * 1024.0,
+                read_latency_ms: 2.0,
+                write_latency_ms: 0.5,
+                queue_depth: 1.5,
+                utilization: 50.0,
+            }
+        );
+    }
+
+    #[test]
+    fn caps_utilization_at_100_percent() {
+        // `io_ticks` can run slightly ahead of the wall clock
+        let after = IoStat {
+            io_ticks: 2100,
+            ..IoStat::default()
+        };
+        let delta = IoDelta::new("sda", &IoStat::default(), &after, TWO_SECONDS);
+        assert_eq!(delta.utilization, 100.0);
+    }
+
+    #[test]
+    fn treats_counters_that_went_back_as_idle() {
+        // The device was re-created between the samples
+        let before = IoStat {
+            read_ios: 500,
+            write_ios: 500,
+            read_sectors: 4000,
+            io_ticks: 900,
+            time_in_queue: 900,
+            ..IoStat::default()
+        };
+        let after = IoStat {
+            read_ios: 10,
+            ..IoStat::default()
+        };
+
+        let delta = IoDelta::new("loop0", &before, &after, TWO_SECONDS);
+        assert_eq!(
+            delta,
+            IoDelta {
+                name: "loop0".to_string(),
+                ..IoDelta::default()
+            }
+        );
+    }
+
+    #[test]
+    fn reports_no_activity_without_elapsed_time() {
+        let after = IoStat {
+            read_ios: 10,
+            io_ticks: 10,
+            ..IoStat::default()
+        };
+        let delta = IoDelta::new("sda", &IoStat::default(), &after, Duration::ZERO);
+        assert_eq!(
+            delta,
+            IoDelta {
+                name: "sda".to_string(),
+                ..IoDelta::default()
+            }
+        );
+    }
+}
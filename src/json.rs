@@ -0,0 +1,779 @@
+use std::fmt;
+
+use super::combined::{CombinedDeviceInfo, CombinedPartitionInfo};
+use super::fstab::Fstab;
+use super::iostat::IoDelta;
+use super::nvme::NvmeController;
+use super::sys_block::{
+    AlignmentOffset, BcacheInfo, DeviceKind, DmInfo, LoopInfo, MdInfo, MdMember, Misalignment,
+    NvmeNamespace, QueueInfo, ScsiInfo, ZonedInfo,
+};
+
+// Bumped whenever a field is renamed, removed or changes its type
+// Adding a new field doesn't bump it, so consumers should ignore unknown keys
+pub const SCHEMA_VERSION: u64 = 1;
+
+// JSON schema of the combined inventory, shaped like `lsblk --json`:
+//
+//     {
+//       "version": 1,
+//       "blockdevices": [
+//         {
+//           "name": "sda",                    string
+//           "maj:min": "8:0",                 string or null
+//           "type": "disk",                   disk, part, loop, dm, md, bcache,
+//                                             zram, nbd, rom, ram or mmcboot
+//           "disk-seq": 12,                   number or null (devices only),
+//                                             changes when a disk is replaced
+//           "model": "Samsung SSD 860",       string or null (devices only)
+//           "vendor": "ATA",                  string or null (devices only)
+//           "serial": "S3Z9NB0K123456",       string or null (devices only)
+//           "wwn": "naa.5002538e40a1b2c3",    string or null (devices only)
+//           "rev": "RVT04B6Q",                string or null (devices only)
+//           "driver": "sd",                   string or null (devices only)
+//           "tran": "sata",                   sata, sas, usb, nvme, virtio, mmc,
+//                                             scsi or null (devices only)
+//           "scsi": {                         SCSI devices only, or null
+//             "hctl": "0:0:0:0",              host:channel:target:lun
+//             "type": "disk",                 SCSI device type, or null
+//             "host": "host0",
+//             "hba": "ahci",                  host driver, or null
+//             "hba-addr": "0000:00:17.0",     PCI address of the adapter, or null
+//             "port": "ata1"                  e.g. ata1, port-0:3, 1-2, or null
+//           },
+//           "size": 512110190592,             bytes, or null
+//           "fstype": "ext4",                 string or null
+//           "label": "root",                  string or null
+//           "uuid": "1111-2222",              first UUID, or null
+//           "uuids": ["1111-2222"],           every UUID (FAT may have two)
+//           "mountpoint": "/",                string or null
+//           "rm": false,                      removable, or null
+//           "ro": false,                      read-only medium (devices only here)
+//           "medium": true,                   false for an empty drive (devices only)
+//           "events": ["media_change"],       media events of the drive (devices only)
+//           "state": "running",               SCSI or NVMe state, or null (devices only)
+//           "queue": {                        request queue, or null
+//             "log-sec": 512,                 logical sector size
+//             "phy-sec": 4096,                physical sector size
+//             "min-io": 4096,                 bytes
+//             "opt-io": 0,                    bytes, 0 when unknown
+//             "alignment": 0,                 bytes (partition's own offset),
+//                                             -1 when it can't be aligned
+//             "disc-aln": 0,                  same as alignment
+//             "rota": false,
+//             "sched": "mq-deadline",         string or null
+//             "ra": 128,                      read-ahead, in KiB
+//             "rq-size": 256,
+//             "disc-gran": 4096,              bytes, 0 without discard support
+//             "disc-max": 2147450880,         bytes
+//             "write-cache": "write back",    string or null
+//             "dax": false
+//           },
+//           "holders": ["dm-0"],              devices built on top of this one
+//           "slaves": [],                     devices this one is built on
+//           "fstab": {                        matching `/etc/fstab` entry, or null
+//             "device": "UUID=1111-2222",
+//             "mountpoint": "/",
+//             "fstype": "ext4",
+//             "options": ["defaults"],
+//             "freq": 0,
+//             "passno": 1
+//           },
+//           "loop": {                         bound loop devices only, or null
+//             "backing-file": "/var/x.img",   string or null
+//             "offset": 0,                    bytes
+//             "sizelimit": 0,                 bytes, 0 means no limit
+//             "autoclear": true,
+//             "partscan": false,
+//             "dio": false
+//           },
+//           "dm": {                           device-mapper devices only, or null
+//             "name": "cryptroot",
+//             "path": "/dev/mapper/cryptroot",
+//             "uuid": "CRYPT-LUKS2-...",      string or null
+//             "target": "crypt",              crypt, lvm, mpath, part, verity or null
+//             "suspended": false
+//           },
+//           "md": {                           software RAID arrays only, or null
+//             "level": "raid1",               string or null
+//             "health": "degraded",           healthy, degraded, syncing or inactive
+//             "array-state": "clean",         string or null
+//             "raid-disks": 2,                number or null
+//             "degraded": 1,                  missing members, or null
+//             "sync-action": "idle",          string or null
+//             "sync-completed": [0, 0],       sectors done and total, or null
+//             "mismatch-cnt": 0,              number or null
+//             "members": [
+//               {"name": "sdb1", "slot": 0, "state": ["in_sync"], "role": "active"}
+//             ]
+//           },
+//           "bcache": {                       bcache devices and members, or null
+//             "role": "device",               device, backing or cache
+//             "cache-set": "f5a1...",         cache set UUID, or null when detached
+//             "devices": ["bcache0"],
+//             "backing": ["sdb"],
+//             "caches": ["nvme0n1p1"],
+//             "mode": "writeback",            string or null (cache side: null)
+//             "state": "clean",               string or null (cache side: null)
+//             "dirty-data": "1.2M",           as printed by the kernel, or null
+//             "sequential-cutoff": "4.0M",    as printed by the kernel, or null
+//             "stats": {                      since registration, or null
+//               "hits": 1200, "misses": 300, "hit-ratio": 80,
+//               "bypass-hits": 0, "bypass-misses": 12
+//             }
+//           },
+//           "nvme": {                         NVMe namespaces only, or null
+//             "nsid": 1,                      number or null
+//             "eui": "00-25-38-...",          string or null
+//             "nguid": "...",                 string or null
+//             "uuid": "...",                  string or null
+//             "controllers": [
+//               {
+//                 "name": "nvme0",
+//                 "model": "...",             string or null
+//                 "serial": "...",            string or null
+//                 "firmware-rev": "...",      string or null
+//                 "transport": "pcie",        string or null
+//                 "subsysnqn": "nqn....",     string or null
+//                 "cntlid": 1,                number or null
+//                 "state": "live"             string or null
+//               }
+//             ]
+//           },
+//           "zoned": {                        zoned devices only, or null
+//             "model": "host-managed",        host-aware or host-managed
+//             "nr-zones": 55880,
+//             "zone-sz": 268435456,           bytes
+//             "zone-omax": 128,               0 means no limit
+//             "zone-amax": 128,               0 means no limit
+//             "zone-app": 0,                  bytes, 0 without zone append
+//             "fs-supported": true            false for e.g. ext4 on host-managed
+//           },
+//           "children": [ ... ]               partitions, same keys as devices
+//                                             except disk-seq, model, identity, scsi,
+//                                             medium, events, state, loop, dm, md,
+//                                             nvme, zoned and children, plus:
+//             "partn": 1,                     number or null
+//             "partlabel": "EFI System",      GPT partition name, or null
+//             "start": 2048,                  first 512-byte sector
+//             "end": 1050623,                 last 512-byte sector
+//             "ro": false,                    read-only partition
+//             "misaligned": null,             offset, phy-sec, opt-io or null
+//             "bcache": null                  same as devices
+//         }
+//       ]
+//     }
+
+// Minimal JSON value, enough to describe our own output
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(i64),
+    Float(f64), // non-finite values are written as null
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>), // keeps insertion order
+}
+
+impl Json {
+    pub fn object<K: Into<String>>(fields: impl IntoIterator<Item = (K, Json)>) -> Self {
+        Self::Object(fields.into_iter().map(|(k, v)| (k.into(), v)).collect())
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<i32> for Json {
+    fn from(value: i32) -> Self {
+        Self::Number(value.into())
+    }
+}
+
+impl From<u64> for Json {
+    fn from(value: u64) -> Self {
+        // Saturate rather than wrap, nothing we report comes close
+        Self::Number(i64::try_from(value).unwrap_or(i64::MAX))
+    }
+}
+
+impl From<AlignmentOffset> for Json {
+    fn from(value: AlignmentOffset) -> Self {
+        Self::Number(value.as_i64())
+    }
+}
+
+impl From<f64> for Json {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<&String> for Json {
+    fn from(value: &String) -> Self {
+        Self::String(value.clone())
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Self::Null, Into::into)
+    }
+}
+
+impl<T: Into<Json>> From<Vec<T>> for Json {
+    fn from(value: Vec<T>) -> Self {
+        Self::Array(value.into_iter().map(Into::into).collect())
+    }
+}
+
+// One `iostat` report, rates between two samples:
+//
+//     {
+//       "version": 1,
+//       "iostat": [
+//         {
+//           "name": "sda",
+//           "r/s": 12.5,                      reads per second
+//           "w/s": 3.0,                       writes per second
+//           "rkb/s": 512.0,                   KiB read per second
+//           "wkb/s": 96.0,                    KiB written per second
+//           "r-await": 0.4,                   ms per read
+//           "w-await": 1.2,                   ms per write
+//           "aqu-sz": 0.01,                   average queue depth
+//           "util": 1.5                       percentage of time busy
+//         }
+//       ]
+//     }
+pub fn iostat(deltas: &[IoDelta]) -> Json {
+    Json::object([
+        ("version", SCHEMA_VERSION.into()),
+        (
+            "iostat",
+            Json::Array(deltas.iter().map(Json::from).collect()),
+        ),
+    ])
+}
+
+impl From<&IoDelta> for Json {
+    fn from(delta: &IoDelta) -> Self {
+        // Rounded like the table, nobody needs 15 decimals
+        let round = |value: f64| Json::Float((value * 100.0).round() / 100.0);
+        Json::object([
+            ("name", Json::from(&delta.name)),
+            ("r/s", round(delta.read_iops)),
+            ("w/s", round(delta.write_iops)),
+            ("rkb/s", round(delta.read_bytes_per_sec / 1024.0)),
+            ("wkb/s", round(delta.write_bytes_per_sec / 1024.0)),
+            ("r-await", round(delta.read_latency_ms)),
+            ("w-await", round(delta.write_latency_ms)),
+            ("aqu-sz", round(delta.queue_depth)),
+            ("util", round(delta.utilization)),
+        ])
+    }
+}
+
+// The whole inventory, see the schema above
+pub fn inventory(devices: &[CombinedDeviceInfo]) -> Json {
+    Json::object([
+        ("version", SCHEMA_VERSION.into()),
+        (
+            "blockdevices",
+            Json::Array(devices.iter().map(Json::from).collect()),
+        ),
+    ])
+}
+
+impl From<&CombinedDeviceInfo> for Json {
+    fn from(device: &CombinedDeviceInfo) -> Self {
+        let mut fields = vec![
+            ("name".to_string(), Json::from(&device.name)),
+            (
+                "maj:min".to_string(),
+                device.devnum.map(|d| d.to_string()).as_ref().into(),
+            ),
+            ("type".to_string(), device.kind.as_str().into()),
+            ("disk-seq".to_string(), device.diskseq.into()),
+            ("model".to_string(), device.model.as_ref().into()),
+        ];
+        let identity = device.identity.clone().unwrap_or_default();
+        fields.extend([
+            ("vendor".to_string(), identity.vendor.as_ref().into()),
+            ("serial".to_string(), identity.serial.as_ref().into()),
+            ("wwn".to_string(), identity.wwid.as_ref().into()),
+            ("rev".to_string(), identity.revision.as_ref().into()),
+            ("driver".to_string(), identity.driver.as_ref().into()),
+            (
+                "tran".to_string(),
+                identity.transport.map(|t| t.as_str()).into(),
+            ),
+        ]);
+        fields.push((
+            "scsi".to_string(),
+            device.scsi.as_ref().map(Json::from).into(),
+        ));
+        fields.extend(common_fields(
+            device.size,
+            device.filesystem.as_ref(),
+            device.label.as_ref(),
+            device.uuids.as_ref(),
+            device.mount_point.as_ref(),
+            device.removable,
+            device.fstab_entry.as_ref(),
+        ));
+        let medium = device.medium.clone().unwrap_or_default();
+        fields.extend([
+            ("ro".to_string(), medium.read_only.into()),
+            ("medium".to_string(), medium.present.into()),
+            (
+                "events".to_string(),
+                Json::Array(medium.events.iter().map(Json::from).collect()),
+            ),
+            ("state".to_string(), medium.device_state.as_ref().into()),
+        ]);
+        fields.push((
+            "queue".to_string(),
+            device.queue.as_ref().map(Json::from).into(),
+        ));
+        fields.extend(relations(&device.holders, &device.slaves));
+        fields.push((
+            "loop".to_string(),
+            device.loop_info.as_ref().map(Json::from).into(),
+        ));
+        fields.push((
+            "dm".to_string(),
+            device.dm_info.as_ref().map(Json::from).into(),
+        ));
+        fields.push((
+            "md".to_string(),
+            device.md_info.as_ref().map(Json::from).into(),
+        ));
+        fields.push((
+            "bcache".to_string(),
+            device.bcache.as_ref().map(Json::from).into(),
+        ));
+        fields.push((
+            "nvme".to_string(),
+            device.nvme.as_ref().map(Json::from).into(),
+        ));
+        fields.push((
+            "zoned".to_string(),
+            device
+                .zoned
+                .as_ref()
+                .map(|zoned| zoned_json(zoned, device.zone_unaware_filesystem().is_none()))
+                .into(),
+        ));
+        fields.push((
+            "children".to_string(),
+            Json::Array(device.partitions.iter().map(Json::from).collect()),
+        ));
+        Self::Object(fields)
+    }
+}
+
+impl From<&CombinedPartitionInfo> for Json {
+    fn from(part: &CombinedPartitionInfo) -> Self {
+        let mut fields = vec![
+            ("name".to_string(), Json::from(&part.name)),
+            (
+                "maj:min".to_string(),
+                part.devnum.map(|d| d.to_string()).as_ref().into(),
+            ),
+            ("type".to_string(), DeviceKind::Partition.as_str().into()),
+        ];
+        let geometry = part.geometry.unwrap_or_default();
+        fields.extend(common_fields(
+            part.size,
+            part.filesystem.as_ref(),
+            part.label.as_ref(),
+            part.uuids.as_ref(),
+            part.mount_point.as_ref(),
+            part.removable,
+            part.fstab_entry.as_ref(),
+        ));
+        fields.push((
+            "queue".to_string(),
+            part.queue.as_ref().map(Json::from).into(),
+        ));
+        fields.extend([
+            ("partn".to_string(), geometry.number.map(u64::from).into()),
+            ("partlabel".to_string(), part.partname.as_ref().into()),
+            ("start".to_string(), geometry.start.into()),
+            ("end".to_string(), geometry.end.into()),
+            ("ro".to_string(), geometry.read_only.into()),
+            (
+                "misaligned".to_string(),
+                part.misalignment().map(Misalignment::as_str).into(),
+            ),
+            (
+                "bcache".to_string(),
+                part.bcache.as_ref().map(Json::from).into(),
+            ),
+        ]);
+        fields.extend(relations(&part.holders, &part.slaves));
+        Self::Object(fields)
+    }
+}
+
+impl From<&LoopInfo> for Json {
+    fn from(loop_info: &LoopInfo) -> Self {
+        Json::object([
+            ("backing-file", loop_info.backing_file.as_ref().into()),
+            ("offset", loop_info.offset.into()),
+            ("sizelimit", loop_info.sizelimit.into()),
+            ("autoclear", loop_info.autoclear.into()),
+            ("partscan", loop_info.partscan.into()),
+            ("dio", loop_info.dio.into()),
+        ])
+    }
+}
+
+impl From<&QueueInfo> for Json {
+    fn from(queue: &QueueInfo) -> Self {
+        Json::object([
+            ("log-sec", queue.logical_block_size.into()),
+            ("phy-sec", queue.physical_block_size.into()),
+            ("min-io", queue.minimum_io_size.into()),
+            ("opt-io", queue.optimal_io_size.into()),
+            ("alignment", queue.alignment_offset.into()),
+            ("disc-aln", queue.discard_alignment.into()),
+            ("rota", queue.rotational.into()),
+            ("sched", queue.scheduler.as_ref().into()),
+            ("ra", queue.read_ahead_kb.into()),
+            ("rq-size", queue.nr_requests.into()),
+            ("disc-gran", queue.discard_granularity.into()),
+            ("disc-max", queue.discard_max_bytes.into()),
+            ("write-cache", queue.write_cache.as_ref().into()),
+            ("dax", queue.dax.into()),
+        ])
+    }
+}
+
+impl From<&DmInfo> for Json {
+    fn from(dm_info: &DmInfo) -> Self {
+        Json::object([
+            ("name", Json::from(&dm_info.name)),
+            ("path", dm_info.mapper_path().as_str().into()),
+            ("uuid", dm_info.uuid.as_ref().into()),
+            ("target", dm_info.target.map(|t| t.as_str()).into()),
+            ("suspended", dm_info.suspended.into()),
+        ])
+    }
+}
+
+impl From<&BcacheInfo> for Json {
+    fn from(bcache: &BcacheInfo) -> Self {
+        let names = |names: &[String]| Json::Array(names.iter().map(Json::from).collect());
+        Json::object([
+            ("role", bcache.role.as_str().into()),
+            ("cache-set", bcache.cache_set.as_ref().into()),
+            ("devices", names(&bcache.devices)),
+            ("backing", names(&bcache.backing)),
+            ("caches", names(&bcache.caches)),
+            ("mode", bcache.cache_mode.as_ref().into()),
+            ("state", bcache.state.as_ref().into()),
+            ("dirty-data", bcache.dirty_data.as_ref().into()),
+            (
+                "sequential-cutoff",
+                bcache.sequential_cutoff.as_ref().into(),
+            ),
+            (
+                "stats",
+                bcache
+                    .stats
+                    .map(|stats| {
+                        Json::object([
+                            ("hits", stats.hits.into()),
+                            ("misses", stats.misses.into()),
+                            ("hit-ratio", stats.hit_ratio.into()),
+                            ("bypass-hits", stats.bypass_hits.into()),
+                            ("bypass-misses", stats.bypass_misses.into()),
+                        ])
+                    })
+                    .into(),
+            ),
+        ])
+    }
+}
+
+impl From<&MdInfo> for Json {
+    fn from(md_info: &MdInfo) -> Self {
+        Json::object([
+            ("level", md_info.level.as_ref().into()),
+            ("health", md_info.health().as_str().into()),
+            ("array-state", md_info.array_state.as_ref().into()),
+            ("raid-disks", md_info.raid_disks.map(u64::from).into()),
+            ("degraded", md_info.degraded.map(u64::from).into()),
+            ("sync-action", md_info.sync_action.as_ref().into()),
+            (
+                "sync-completed",
+                md_info
+                    .sync_completed
+                    .map(|(done, total)| vec![done, total])
+                    .into(),
+            ),
+            ("mismatch-cnt", md_info.mismatch_cnt.into()),
+            (
+                "members",
+                Json::Array(md_info.members.iter().map(Json::from).collect()),
+            ),
+        ])
+    }
+}
+
+impl From<&MdMember> for Json {
+    fn from(member: &MdMember) -> Self {
+        Json::object([
+            ("name", Json::from(&member.name)),
+            ("slot", member.slot.map(u64::from).into()),
+            (
+                "state",
+                Json::Array(member.state.iter().map(Json::from).collect()),
+            ),
+            ("role", member.role().into()),
+        ])
+    }
+}
+
+fn zoned_json(zoned: &ZonedInfo, fs_supported: bool) -> Json {
+    Json::object([
+        ("model", zoned.model.as_str().into()),
+        ("nr-zones", zoned.nr_zones.into()),
+        ("zone-sz", zoned.zone_size.into()),
+        ("zone-omax", zoned.max_open_zones.into()),
+        ("zone-amax", zoned.max_active_zones.into()),
+        ("zone-app", zoned.zone_append_max_bytes.into()),
+        ("fs-supported", fs_supported.into()),
+    ])
+}
+
+impl From<&ScsiInfo> for Json {
+    fn from(scsi: &ScsiInfo) -> Self {
+        Json::object([
+            ("hctl", scsi.address.to_string().as_str().into()),
+            ("type", scsi.type_name().into()),
+            ("host", scsi.host.as_str().into()),
+            ("hba", scsi.host_driver.as_ref().into()),
+            ("hba-addr", scsi.adapter.as_ref().into()),
+            ("port", scsi.port.as_ref().into()),
+        ])
+    }
+}
+
+impl From<&NvmeNamespace> for Json {
+    fn from(nvme: &NvmeNamespace) -> Self {
+        Json::object([
+            ("nsid", nvme.nsid.map(u64::from).into()),
+            ("eui", nvme.eui.as_ref().into()),
+            ("nguid", nvme.nguid.as_ref().into()),
+            ("uuid", nvme.uuid.as_ref().into()),
+            (
+                "controllers",
+                Json::Array(nvme.controllers.iter().map(Json::from).collect()),
+            ),
+        ])
+    }
+}
+
+impl From<&NvmeController> for Json {
+    fn from(controller: &NvmeController) -> Self {
+        Json::object([
+            ("name", Json::from(&controller.name)),
+            ("model", controller.model.as_ref().into()),
+            ("serial", controller.serial.as_ref().into()),
+            ("firmware-rev", controller.firmware_rev.as_ref().into()),
+            ("transport", controller.transport.as_ref().into()),
+            ("subsysnqn", controller.subsysnqn.as_ref().into()),
+            ("cntlid", controller.cntlid.map(u64::from).into()),
+            ("state", controller.state.as_ref().into()),
+        ])
+    }
+}
+
+impl From<&Fstab> for Json {
+    fn from(entry: &Fstab) -> Self {
+        Json::object([
+            ("device", Json::from(&entry.device)),
+            ("mountpoint", (&entry.mount_point).into()),
+            ("fstype", (&entry.fs_type).into()),
+            (
+                "options",
+                Json::Array(entry.options.iter().map(Json::from).collect()),
+            ),
+            ("freq", entry.dump_freq.into()),
+            ("passno", entry.fsck_pass.into()),
+        ])
+    }
+}
+
+fn common_fields(
+    size: Option<u64>,
+    filesystem: Option<&String>,
+    label: Option<&String>,
+    uuids: Option<&Vec<String>>,
+    mount_point: Option<&String>,
+    removable: Option<bool>,
+    fstab_entry: Option<&Fstab>,
+) -> Vec<(String, Json)> {
+    vec![
+        ("size".to_string(), size.into()),
+        ("fstype".to_string(), filesystem.into()),
+        ("label".to_string(), label.into()),
+        ("uuid".to_string(), uuids.and_then(|u| u.first()).into()),
+        (
+            "uuids".to_string(),
+            Json::Array(uuids.into_iter().flatten().map(Json::from).collect()),
+        ),
+        ("mountpoint".to_string(), mount_point.into()),
+        ("rm".to_string(), removable.into()),
+        ("fstab".to_string(), fstab_entry.map(Json::from).into()),
+    ]
+}
+
+fn relations(holders: &[String], slaves: &[String]) -> Vec<(String, Json)> {
+    vec![
+        (
+            "holders".to_string(),
+            Json::Array(holders.iter().map(Json::from).collect()),
+        ),
+        (
+            "slaves".to_string(),
+            Json::Array(slaves.iter().map(Json::from).collect()),
+        ),
+    ]
+}
+
+// Pretty printed with 2 spaces, like `lsblk --json`
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_value(f, self, 0)
+    }
+}
+
+fn write_value(f: &mut fmt::Formatter<'_>, value: &Json, depth: usize) -> fmt::Result {
+    let indent = "  ".repeat(depth + 1);
+    let closing = "  ".repeat(depth);
+
+    match value {
+        Json::Null => f.write_str("null"),
+        Json::Bool(b) => write!(f, "{b}"),
+        Json::Number(n) => write!(f, "{n}"),
+        Json::Float(x) if x.is_finite() => write!(f, "{x}"),
+        Json::Float(_) => f.write_str("null"),
+        Json::String(s) => write_string(f, s),
+        Json::Array(items) if items.is_empty() => f.write_str("[]"),
+        Json::Array(items) => {
+            writeln!(f, "[")?;
+            for (i, item) in items.iter().enumerate() {
+                f.write_str(&indent)?;
+                write_value(f, item, depth + 1)?;
+                writeln!(f, "{}", if i + 1 < items.len() { "," } else { "" })?;
+            }
+            write!(f, "{closing}]")
+        }
+        Json::Object(fields) if fields.is_empty() => f.write_str("{}"),
+        Json::Object(fields) => {
+            writeln!(f, "{{")?;
+            for (i, (key, item)) in fields.iter().enumerate() {
+                f.write_str(&indent)?;
+                write_string(f, key)?;
+                f.write_str(": ")?;
+                write_value(f, item, depth + 1)?;
+                writeln!(f, "{}", if i + 1 < fields.len() { "," } else { "" })?;
+            }
+            write!(f, "{closing}}}")
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{c}")?,
+        }
+    }
+    f.write_str("\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_strings() {
+        let value = Json::from("a \"quoted\" C:\\path\n\tand\u{1b}[0m é");
+        assert_eq!(
+            value.to_string(),
+            r#""a \"quoted\" C:\\path\n\tand\u001b[0m é""#
+        );
+    }
+
+    #[test]
+    fn writes_empty_containers_inline() {
+        let value = Json::object([
+            ("holders", Json::Array(Vec::new())),
+            ("fstab", Json::Object(Vec::new())),
+        ]);
+        assert_eq!(
+            value.to_string(),
+            "{\n  \"holders\": [],\n  \"fstab\": {}\n}"
+        );
+    }
+
+    #[test]
+    fn writes_non_finite_floats_as_null() {
+        let value = Json::Array(vec![
+            Json::Float(1.5),
+            Json::Float(f64::NAN),
+            Json::Float(f64::INFINITY),
+            Json::Float(f64::NEG_INFINITY),
+        ]);
+        assert_eq!(value.to_string(), "[\n  1.5,\n  null,\n  null,\n  null\n]");
+    }
+
+    #[test]
+    fn indents_nested_values() {
+        let value = Json::object([
+            ("name", Json::from("sda")),
+            ("rm", Json::Bool(false)),
+            (
+                "children",
+                Json::Array(vec![Json::object([
+                    ("name", Json::from("sda1")),
+                    ("partn", Json::Number(1)),
+                    ("label", Json::Null),
+                ])]),
+            ),
+        ]);
+        let expected = r#"{
+  "name": "sda",
+  "rm": false,
+  "children": [
+    {
+      "name": "sda1",
+      "partn": 1,
+      "label": null
+    }
+  ]
+}"#;
+        assert_eq!(value.to_string(), expected);
+    }
+}
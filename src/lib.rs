@@ -0,0 +1,53 @@
+//! Gather block device information from `/sys/block`, `/dev/disk`,
+//! `/proc/mounts` and `/etc/fstab`, and merge it into a single inventory
+//!
+//! Each source has its own collector, `CombinedDeviceInfo` joins them
+//! and `get_fstype_with_magic` probes a device for a filesystem signature
+//!
+//! `NvmeInfo` lists NVMe controllers from `/sys/class/nvme` along with
+//! their namespaces, and `ScsiHosts` groups SCSI devices under their host
+//! adapter
+//!
+//! `IoSample` reads the I/O counters of the same devices, two samples
+//! give their activity in between
+
+pub mod columns;
+pub mod combined;
+pub mod dev_disk;
+pub mod devnum;
+mod display;
+pub mod error;
+#[cfg(test)]
+mod fixture;
+pub mod fstab;
+pub mod graph;
+pub mod iostat;
+pub mod json;
+pub mod magic;
+pub mod nvme;
+pub mod paths;
+pub mod proc_mounts;
+pub mod sys_block;
+
+pub use columns::{Column, Table};
+pub use combined::{
+    CombinedDeviceInfo, CombinedPartitionInfo, LoopSummary, ScsiHosts, collapse_image_loops,
+};
+pub use dev_disk::{DevDisk, DevDiskInfo};
+pub use devnum::DevNum;
+pub use error::{Error, Result, Source};
+pub use fstab::{Fstab, FstabInfo};
+pub use graph::{Node, NodeId, NodeInfo, StorageGraph};
+pub use iostat::{IoDelta, IoSample, IoStat, sample_delta};
+pub use json::Json;
+pub use magic::{FsType, get_fstype_with_magic, probe_fstype};
+pub use nvme::{NvmeController, NvmeInfo};
+pub use paths::SysPaths;
+pub use proc_mounts::{ProcMounts, ProcMountsInfo};
+pub use sys_block::{
+    AlignmentOffset, BcacheInfo, BcacheRole, BcacheStats, DeviceKind, DmInfo, DmTarget,
+    HardwareIdentity, LoopInfo, MdHealth, MdInfo, MdMember, MediumState, Misalignment,
+    NvmeNamespace, PartitionGeometry, QueueInfo, ScsiAddress, ScsiInfo, SysBlockDevice,
+    SysBlockInfo, SysBlockPartition, Transport, ZONE_AWARE_FILESYSTEMS, ZoneModel, ZonedInfo,
+    resolve_device_name,
+};
@@ -1,7 +1,11 @@
 use std::fs::File;
-use std::io::{Error, Read, Seek, SeekFrom};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
 use tracing::{info, warn};
 
+use super::error::{Error, Result, Source};
+use super::paths::SysPaths;
+
 // Filesystem magic numbers
 // source: <https://github.com/torvalds/linux/blob/master/include/uapi/linux/magic.h>
 // another source: <https://github.com/file/file/blob/master/magic/Magdir/filesystems>
@@ -60,10 +64,15 @@ struct Signature {
 
 // Get filesystem type using magic numbers
 // It seems to require root privileges ☹
+#[tracing::instrument(skip(paths))]
+pub fn get_fstype_with_magic(paths: &SysPaths, device: &str) -> Result<Option<String>> {
+    probe_fstype(&paths.dev.join(device)).map_err(|e| e.with_device(device))
+}
+
+// Same as `get_fstype_with_magic`, but for any path (e.g. a disk image)
 #[tracing::instrument]
-pub fn get_fstype_with_magic(device: &str) -> Result<Option<String>, Error> {
-    let path = format!("/dev/{device}");
-    let mut file = File::open(&path)?;
+pub fn probe_fstype(path: &Path) -> Result<Option<String>> {
+    let mut file = File::open(path).map_err(|e| Error::io(Source::Magic, path, e))?;
 
     let signatures = vec![
         Signature {
@@ -192,10 +201,10 @@ pub fn get_fstype_with_magic(device: &str) -> Result<Option<String>, Error> {
                 }
 
                 // Check boot sector signature
-                if let Some(check_fn) = secondary_check {
-                    if !check_fn(&buffer) {
-                        continue;
-                    }
+                if let Some(check_fn) = secondary_check
+                    && !check_fn(&buffer)
+                {
+                    continue;
                 }
 
                 // Check the signature itself
@@ -262,7 +271,7 @@ pub fn get_fstype_with_magic(device: &str) -> Result<Option<String>, Error> {
         }
     }
 
-    warn!("Could not determine fs type for `{device}`");
+    warn!("Could not determine fs type for `{}`", path.display());
     Ok(None)
 }
 
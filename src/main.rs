@@ -1,42 +1,325 @@
-mod combined;
-mod dev_disk;
-mod display;
-mod fstab;
-mod magic;
-mod proc_mounts;
-mod sys_block;
-
-use combined::CombinedDeviceInfo;
-use dev_disk::DevDiskInfo;
-use fstab::FstabInfo;
-use proc_mounts::ProcMountsInfo;
-use sys_block::SysBlockInfo;
+mod cli;
 
+use std::{path::Path, process::ExitCode, thread, time::Duration};
+
+use cli::{Args, Command, OutputFormat, SourceArg};
+use rutiles::{
+    Column, CombinedDeviceInfo, DevDiskInfo, Error, FstabInfo, IoSample, NvmeInfo, ProcMountsInfo,
+    ScsiHosts, SysBlockInfo, SysPaths, Table, collapse_image_loops, columns, get_fstype_with_magic,
+    json, probe_fstype, resolve_device_name,
+};
+
+use tracing::{Level, enabled, warn};
 use tracing_subscriber::{EnvFilter, fmt};
 
-fn main() {
+fn main() -> ExitCode {
+    let mut args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("rutiles: {e}");
+            eprintln!("Try `rutiles --help` for more information");
+            return ExitCode::from(2);
+        }
+    };
+
+    init_tracing(args.verbose);
+
+    let paths = args
+        .root
+        .as_ref()
+        .map_or_else(SysPaths::new, SysPaths::with_root);
+
+    // Selected devices are compared by their kernel name from now on
+    args.devices = args
+        .devices
+        .iter()
+        .map(|device| resolve_device_name(&paths, device))
+        .collect();
+
+    let result = match &args.command {
+        Command::Help => {
+            print!("{}", cli::USAGE);
+            Ok(())
+        }
+        Command::List => list(&paths, &args),
+        Command::Sources(source) => sources(&paths, &args, *source),
+        Command::Scsi => scsi(&paths, &args),
+        Command::Probe(target) => probe(&paths, target),
+        Command::IoStat { interval, count } => iostat(&paths, &args, *interval, *count),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("rutiles: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+// `RUST_LOG` takes precedence over `-v`
+fn init_tracing(verbose: u8) {
+    let level = match verbose {
+        0 => "error",
+        1 => "warn",
+        2 => "info",
+        3 => "debug",
+        _ => "trace",
+    };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(level));
+
     tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env())
+        .with_env_filter(filter)
         .with_span_events(fmt::format::FmtSpan::CLOSE)
+        .with_writer(std::io::stderr)
         .init();
+}
 
-    let sys_block_info = SysBlockInfo::new().ok().unwrap();
-    let dev_disk_info = DevDiskInfo::new().ok().unwrap();
-    let proc_mounts_info = ProcMountsInfo::new().ok().unwrap();
-    let fstab_info = FstabInfo::new().ok().unwrap();
+// Combined view of every device, shared by `list` and `scsi`
+fn inventory(paths: &SysPaths) -> Result<Vec<CombinedDeviceInfo>, Error> {
+    // Without `/sys/block` there is nothing to show
+    let sys_block_info = SysBlockInfo::new(paths)?;
+    // The other sources only add details, so carry on without them
+    let mut failed_sources = Vec::new();
+    let dev_disk_info = DevDiskInfo::new(paths).unwrap_or_else(|e| {
+        failed_sources.push(e);
+        DevDiskInfo::default()
+    });
+    let proc_mounts_info = ProcMountsInfo::new(paths).unwrap_or_else(|e| {
+        failed_sources.push(e);
+        ProcMountsInfo::default()
+    });
+    let fstab_info = FstabInfo::new(paths).unwrap_or_else(|e| {
+        failed_sources.push(e);
+        FstabInfo::default()
+    });
 
-    // print!("{sys_block_info}");
-    // print!("{dev_disk_info}");
-    // print!("{proc_mounts_info}");
+    report(
+        failed_sources
+            .iter()
+            .chain(&sys_block_info.diagnostics)
+            .chain(&dev_disk_info.diagnostics),
+    );
 
-    let combined_device_info = CombinedDeviceInfo::new(
+    Ok(CombinedDeviceInfo::new(
+        paths,
         &sys_block_info,
         &dev_disk_info,
         &proc_mounts_info,
         &fstab_info,
-    );
+    ))
+}
+
+fn list(paths: &SysPaths, args: &Args) -> Result<(), Error> {
+    let mut combined_device_info = inventory(paths)?;
+
+    // Keep a device when it's selected, or when one of its partitions is
+    if !args.devices.is_empty() {
+        combined_device_info.retain_mut(|device| {
+            if is_selected(&args.devices, &device.name) {
+                return true;
+            }
+            device
+                .partitions
+                .retain(|part| is_selected(&args.devices, &part.name));
+            !device.partitions.is_empty()
+        });
+    }
+
+    if args.hide_unused_loops {
+        combined_device_info.retain(|device| !device.is_unused_loop());
+    }
+    let loop_summary = args
+        .collapse_loops
+        .then(|| collapse_image_loops(&mut combined_device_info))
+        .filter(|summary| summary.count > 0);
+
+    match args.format {
+        OutputFormat::Text => {
+            for device in &combined_device_info {
+                if args.verbose > 0 {
+                    println!("{device:#}");
+                } else {
+                    println!("{device}");
+                }
+            }
+            if let Some(summary) = loop_summary {
+                print!("{summary}");
+            }
+        }
+        OutputFormat::Table => {
+            let table = Table {
+                columns: args
+                    .columns
+                    .clone()
+                    .unwrap_or_else(|| Column::DEFAULT.to_vec()),
+                headings: args.headings,
+                tree: args.tree,
+            };
+            print!("{}", table.render(&combined_device_info));
+            if let Some(summary) = loop_summary {
+                print!("{summary}");
+            }
+        }
+        OutputFormat::Json => println!("{}", json::inventory(&combined_device_info)),
+        // Every column by default, scripts pick what they need
+        OutputFormat::Pairs => {
+            let columns = args.columns.as_deref().unwrap_or(Column::ALL);
+            print!("{}", columns::pairs(columns, &combined_device_info));
+        }
+    }
+
+    Ok(())
+}
 
-    for device in combined_device_info {
-        println!("{device}");
+// Like `lsblk --scsi`, only the devices behind a SCSI host, without partitions
+fn scsi(paths: &SysPaths, args: &Args) -> Result<(), Error> {
+    let mut devices = inventory(paths)?;
+    devices.retain(|device| {
+        device.scsi.is_some()
+            && (args.devices.is_empty() || is_selected(&args.devices, &device.name))
+    });
+    for device in &mut devices {
+        device.partitions.clear();
     }
+    devices.sort_by_key(|device| device.scsi.as_ref().map(|scsi| scsi.address));
+
+    let columns = args.columns.as_deref().unwrap_or(Column::SCSI);
+    match args.format {
+        OutputFormat::Text => print!("{}", ScsiHosts { devices: &devices }),
+        OutputFormat::Table => {
+            let table = Table {
+                columns: columns.to_vec(),
+                headings: args.headings,
+                tree: false,
+            };
+            print!("{}", table.render(&devices));
+        }
+        OutputFormat::Json => println!("{}", json::inventory(&devices)),
+        OutputFormat::Pairs => print!("{}", columns::pairs(columns, &devices)),
+    }
+
+    Ok(())
+}
+
+// Dump a single source with its own `Display` implementation
+fn sources(paths: &SysPaths, args: &Args, source: SourceArg) -> Result<(), Error> {
+    let selected = |name: &str| args.devices.is_empty() || is_selected(&args.devices, name);
+
+    match source {
+        SourceArg::SysBlock => {
+            let mut info = SysBlockInfo::new(paths)?;
+            report(&info.diagnostics);
+            info.info.retain(|device| {
+                selected(&device.name)
+                    || device
+                        .part
+                        .iter()
+                        .flatten()
+                        .any(|part| selected(&part.name))
+            });
+            print!("{info}");
+        }
+        SourceArg::DevDisk => {
+            let mut info = DevDiskInfo::new(paths)?;
+            report(&info.diagnostics);
+            info.info.retain(|device| selected(&device.name));
+            print!("{info}");
+        }
+        SourceArg::Mounts => {
+            let mut info = ProcMountsInfo::new(paths)?;
+            // e.g. `mapper/cryptroot` for `dm-0`
+            info.info
+                .retain(|mount| selected(&resolve_device_name(paths, &mount.name)));
+            print!("{info}");
+        }
+        SourceArg::Fstab => {
+            print!("{}", FstabInfo::new(paths)?);
+        }
+        // Controllers are kept when one of their namespaces is selected
+        SourceArg::Nvme => {
+            let mut info = NvmeInfo::new(paths)?;
+            report(&info.diagnostics);
+            info.info.retain(|controller| {
+                selected(&controller.name)
+                    || controller.namespaces.iter().any(|name| selected(name))
+            });
+            print!("{info}");
+        }
+    }
+
+    Ok(())
+}
+
+// Anything that looks like a path (or an existing file) is probed as is,
+//     otherwise it's a device name (kernel or mapper name)
+fn probe(paths: &SysPaths, target: &str) -> Result<(), Error> {
+    let is_file =
+        !target.starts_with("/dev/") && (target.contains('/') || Path::new(target).is_file());
+    let fstype = if is_file {
+        probe_fstype(Path::new(target))?
+    } else {
+        get_fstype_with_magic(paths, &resolve_device_name(paths, target))?
+    };
+
+    println!("{target}: {}", fstype.as_deref().unwrap_or("unknown"));
+    Ok(())
+}
+
+// Each report covers the previous interval, like `iostat -x INTERVAL`
+fn iostat(
+    paths: &SysPaths,
+    args: &Args,
+    interval: Duration,
+    count: Option<u64>,
+) -> Result<(), Error> {
+    let sys_block_info = SysBlockInfo::new(paths)?;
+    report(&sys_block_info.diagnostics);
+
+    let mut before = IoSample::new(paths, &sys_block_info);
+    let mut reports = 0;
+    while count.is_none_or(|count| reports < count) {
+        thread::sleep(interval);
+        let after = IoSample::new(paths, &sys_block_info);
+        report(&after.diagnostics);
+
+        let mut deltas = after.delta_since(&before);
+        if !args.devices.is_empty() {
+            deltas.retain(|delta| is_selected(&args.devices, &delta.name));
+        }
+        if reports > 0 {
+            println!();
+        }
+        match args.format {
+            OutputFormat::Json => println!("{}", json::iostat(&deltas)),
+            _ => print!("{}", rutiles::iostat::render(&deltas)),
+        }
+
+        before = after;
+        reports += 1;
+    }
+
+    Ok(())
+}
+
+// Diagnostics are warnings, which are hidden by default, so at least
+//     say that something was left out
+fn report<'a>(diagnostics: impl IntoIterator<Item = &'a Error>) {
+    if enabled!(Level::WARN) {
+        diagnostics.into_iter().for_each(|d| warn!("{d}"));
+        return;
+    }
+    let count = diagnostics.into_iter().count();
+    if count > 0 {
+        let plural = if count == 1 { "" } else { "s" };
+        eprintln!(
+            "rutiles: {count} device{plural} or attribute{plural} couldn't be read, \
+             rerun with `-v` for details"
+        );
+    }
+}
+
+// Devices were already resolved to their kernel name, see `resolve_device_name`
+fn is_selected(devices: &[String], name: &str) -> bool {
+    devices.iter().any(|device| device == name)
 }
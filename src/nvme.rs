@@ -0,0 +1,144 @@
+use std::{fs, io::ErrorKind, path::Path};
+
+use super::error::{Error, Result, Source};
+use super::paths::SysPaths;
+use super::sys_block::{numbered, read_optional_attr_from, read_parsed_or_report_from};
+
+// `/sys/class/nvme/nvme{n}/` entries
+// A controller exposes one or more namespaces, each of them being a block
+//     device (e.g. `nvme0n1`, `nvme0n2`)
+#[derive(Debug, Clone)]
+pub struct NvmeController {
+    pub name: String,          // e.g. `nvme0`
+    pub model: Option<String>, // e.g. `Samsung SSD 980 PRO 1TB`
+    pub serial: Option<String>,
+    pub firmware_rev: Option<String>, // e.g. `5B2QGXA7`
+    pub transport: Option<String>,    // e.g. `pcie`, `tcp`, `rdma`, `fc`, `loop`
+    pub subsysnqn: Option<String>,    // NVMe Qualified Name of the subsystem
+    pub cntlid: Option<u16>,          // controller ID within the subsystem
+    pub state: Option<String>,        // e.g. `live`, `resetting`, `dead`
+    pub namespaces: Vec<String>,      // block devices, e.g. `["nvme0n1", "nvme0n2"]`
+}
+
+// Pack all the information
+// A controller that can't be read is left out and its error is kept in
+//     `diagnostics`, machines without NVMe simply have no controllers
+#[derive(Debug, Default)]
+pub struct NvmeInfo {
+    pub info: Vec<NvmeController>,
+    pub diagnostics: Vec<Error>,
+}
+
+impl NvmeInfo {
+    pub fn new(paths: &SysPaths) -> Result<Self> {
+        let mut info = Vec::new();
+        let mut diagnostics = Vec::new();
+
+        let entries = match fs::read_dir(&paths.sys_class_nvme) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(Error::io(Source::Nvme, &paths.sys_class_nvme, e)),
+        };
+        for entry in entries {
+            let entry = entry.map_err(|e| Error::io(Source::Nvme, &paths.sys_class_nvme, e))?;
+            match NvmeController::new(&entry.path(), &mut diagnostics) {
+                Ok(controller) => info.push(controller),
+                Err(e) => diagnostics.push(e),
+            }
+        }
+        info.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(Self { info, diagnostics })
+    }
+}
+
+impl NvmeController {
+    // `path` is either `/sys/class/nvme/nvme{n}` or the `device` of a namespace
+    // Attributes that don't parse are reported in `diagnostics`
+    pub fn new(path: &Path, diagnostics: &mut Vec<Error>) -> Result<Self> {
+        let name = fs::canonicalize(path)
+            .unwrap_or_else(|_| path.to_path_buf())
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .ok_or_else(|| Error::parse(Source::Nvme, path, "Invalid controller path"))?;
+        // A missing or empty attribute is `None`
+        let read = |attr: &str| {
+            read_optional_attr_from(Source::Nvme, &path.join(attr))
+                .map(|value| value.filter(|value| !value.is_empty()))
+                .map_err(|e| e.with_device(&name))
+        };
+
+        let mut details = Vec::new();
+        let cntlid = read_parsed_or_report_from(Source::Nvme, &path.join("cntlid"), &mut details)
+            .map_err(|e| e.with_device(&name))?;
+        diagnostics.extend(details.into_iter().map(|e| e.with_device(&name)));
+
+        Ok(Self {
+            model: read("model")?,
+            serial: read("serial")?,
+            firmware_rev: read("firmware_rev")?,
+            transport: read("transport")?,
+            subsysnqn: read("subsysnqn")?,
+            cntlid,
+            state: read("state")?,
+            namespaces: namespaces(path, &name),
+            name,
+        })
+    }
+}
+
+// Namespaces show up as entries of the controller directory, either as
+//     `nvme0n1` or, with native multipath, as the path `nvme0c0n1` of the
+//     shared `nvme0n1` block device
+fn namespaces(path: &Path, controller: &str) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(path) else {
+        return Vec::new();
+    };
+
+    let mut namespaces: Vec<String> = entries
+        .filter_map(std::result::Result::ok)
+        .filter_map(|entry| namespace_name(&entry.file_name().to_string_lossy(), controller))
+        .collect();
+    namespaces.sort();
+    namespaces.dedup();
+    namespaces
+}
+
+// `nvme{subsystem}c{controller}n{namespace}` → `nvme{subsystem}n{namespace}`
+fn namespace_name(entry: &str, controller: &str) -> Option<String> {
+    let digits = |s: &str| numbered(s, "");
+    let rest = entry.strip_prefix("nvme")?;
+    let (head, nsid) = rest.split_once('n')?;
+    if !digits(nsid) {
+        return None;
+    }
+    match head.split_once('c') {
+        Some((subsystem, path)) if digits(subsystem) && digits(path) => {
+            Some(format!("nvme{subsystem}n{nsid}"))
+        }
+        None if controller.strip_prefix("nvme") == Some(head) => Some(entry.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_namespaces_after_their_block_device() {
+        assert_eq!(
+            namespace_name("nvme0n1", "nvme0").as_deref(),
+            Some("nvme0n1")
+        );
+        // Native multipath, the path of `nvme0n1` through controller 1
+        assert_eq!(
+            namespace_name("nvme0c1n1", "nvme1").as_deref(),
+            Some("nvme0n1")
+        );
+        // Another controller's namespace
+        assert_eq!(namespace_name("nvme1n1", "nvme0"), None);
+        assert_eq!(namespace_name("nvme0n", "nvme0"), None);
+        assert_eq!(namespace_name("hwmon0", "nvme0"), None);
+    }
+}
@@ -0,0 +1,42 @@
+use std::path::{Path, PathBuf};
+
+// Location of every source we read from
+// By default everything points to the running system, but the whole set can
+//     be rebased on another root (e.g. a chroot, a mounted rescue image,
+//     a container rootfs or a directory of captured fixtures)
+// Each path is public so a single source can still be overridden afterwards
+#[derive(Debug, Clone)]
+pub struct SysPaths {
+    pub root: PathBuf,           // e.g. `/`, the one everything else was built from
+    pub sys_block: PathBuf,      // e.g. `/sys/block`
+    pub dev_disk: PathBuf,       // e.g. `/dev/disk`
+    pub proc_mounts: PathBuf,    // e.g. `/proc/mounts`
+    pub fstab: PathBuf,          // e.g. `/etc/fstab`
+    pub dev: PathBuf,            // e.g. `/dev`, where device nodes are opened for probing
+    pub sys_class_nvme: PathBuf, // e.g. `/sys/class/nvme`
+}
+
+impl SysPaths {
+    pub fn new() -> Self {
+        Self::with_root("/")
+    }
+
+    pub fn with_root(root: impl AsRef<Path>) -> Self {
+        let root = root.as_ref();
+        Self {
+            root: root.to_path_buf(),
+            sys_block: root.join("sys/block"),
+            dev_disk: root.join("dev/disk"),
+            proc_mounts: root.join("proc/mounts"),
+            fstab: root.join("etc/fstab"),
+            dev: root.join("dev"),
+            sys_class_nvme: root.join("sys/class/nvme"),
+        }
+    }
+}
+
+impl Default for SysPaths {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,26 +1,34 @@
 use std::fs;
-use std::io::Result;
+use std::path::Path;
+
+use super::devnum::DevNum;
+use super::error::{Error, Result, Source};
+use super::paths::SysPaths;
 
 // `/proc/mounts` information abstraction for devices and partitions
 // Information is obtained by parsing the file content
 // Each line represents a different mount (e. g. `/dev/sdc /mnt/usb ext4 rw,relatime 0 0`)
 // We only retrieve entries (mounts) that start with `/dev/`
+// The source is also resolved to a device number, since its name often isn't
+//     the kernel one (e.g. `/dev/mapper/cryptroot` for `dm-0`, or `/dev/root`)
 #[derive(Debug)]
 pub struct ProcMounts {
-    pub name: String,        // first "field"
-    pub mount_point: String, // second "field"
-    pub fstype: String,      // third "field"
+    pub name: String,           // first "field", without `/dev/`
+    pub mount_point: String,    // second "field"
+    pub fstype: String,         // third "field"
+    pub devnum: Option<DevNum>, // from `stat` on the source, or on the mount point
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ProcMountsInfo {
     pub info: Vec<ProcMounts>,
 }
 
 impl ProcMountsInfo {
-    pub fn new() -> Result<Self> {
+    pub fn new(paths: &SysPaths) -> Result<Self> {
         let mut info: Vec<ProcMounts> = Vec::new();
-        let mounts = fs::read_to_string("/proc/mounts")?;
+        let mounts = fs::read_to_string(&paths.proc_mounts)
+            .map_err(|e| Error::io(Source::ProcMounts, &paths.proc_mounts, e))?;
 
         for line in mounts.lines() {
             let fields: Vec<&str> = line.split_whitespace().collect();
@@ -31,7 +39,13 @@ impl ProcMountsInfo {
                     let trimmed_name = dev_name
                         .strip_prefix("/dev/")
                         .expect("starts_with guaranteed");
-                    let entry = ProcMounts::new(trimmed_name, fields[1], fields[2]);
+                    let mut entry = ProcMounts::new(trimmed_name, fields[1], fields[2]);
+                    entry.devnum = DevNum::of_block_device(&paths.dev.join(trimmed_name));
+                    // e.g. `/dev/root` usually doesn't exist, but the mount point does
+                    // Only on the live system, other roots don't own their mount points
+                    if entry.devnum.is_none() && paths.root == Path::new("/") {
+                        entry.devnum = DevNum::of_filesystem(Path::new(&entry.mount_point));
+                    }
                     info.push(entry);
                 }
             }
@@ -46,6 +60,7 @@ impl ProcMounts {
             name: name.into(),
             mount_point: mount_point.into(),
             fstype: fstype.into(),
+            devnum: None,
         }
     }
 }
@@ -1,9 +1,44 @@
+mod bcache;
+mod dm_info;
+mod geometry;
+mod identity;
+mod loop_info;
+mod md_info;
+mod medium;
+mod nvme;
+mod queue;
+mod scsi;
+mod uevent;
+mod zoned;
+
 use std::{
-    fs,
-    io::{Error, ErrorKind, Result},
-    path::{Path, PathBuf},
+    fmt, fs,
+    io::ErrorKind,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
 };
 
+use super::devnum::DevNum;
+use super::error::{Error, Result, Source};
+use super::paths::SysPaths;
+
+pub use bcache::{BcacheInfo, BcacheRole, BcacheStats};
+pub use dm_info::{DmInfo, DmTarget};
+pub use geometry::{Misalignment, PartitionGeometry};
+pub use identity::{HardwareIdentity, Transport};
+pub use loop_info::LoopInfo;
+pub use md_info::{MdHealth, MdInfo, MdMember};
+pub use medium::MediumState;
+pub use nvme::NvmeNamespace;
+pub use queue::{AlignmentOffset, QueueInfo};
+pub use scsi::{ScsiAddress, ScsiInfo};
+pub use zoned::{ZONE_AWARE_FILESYSTEMS, ZoneModel, ZonedInfo};
+
+use uevent::Uevent;
+
+// Sysfs always counts in 512-byte sectors, whatever the logical block size
+pub(crate) const SECTOR_SIZE: u64 = 512;
+
 // `/sys/block/` entries, stored in an array
 //    Each symlink `PathBuf` represents a device
 #[derive(Debug)]
@@ -11,13 +46,48 @@ pub struct SysBlockEntries {
     pub block_devices: Vec<PathBuf>, // e.g. `["/sys/block/nvme0n1", "/sys/block/sda"]`
 }
 
+// What kind of block device an entry is
+// Everything in `/sys/block` used to be treated as a disk, but most virtual
+//     devices only differ from one by their major number or a sysfs directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceKind {
+    Disk,
+    Partition,
+    Loop,    // major 7, has a `loop/` directory
+    Dm,      // has a `dm/` directory, major is dynamic
+    Md,      // major 9, has a `md/` directory
+    Bcache,  // `bcache*`, its `bcache/` is a link to the backing device's
+    Zram,    // `zram*`, major is dynamic
+    Nbd,     // major 43
+    Rom,     // major 11 (`sr*`), or SCSI device type 5
+    Ram,     // major 1 (`ram*`, from the `brd` module)
+    MmcBoot, // eMMC boot areas, e.g. `mmcblk0boot0`
+}
+
 // `/sys/block/{device}/` entries
-//    note: any partition will appear as an entry here (e.g. `sda1/`)
+//    note: any partition will appear as an entry here (e.g. `sda1/`),
+//    told apart from other directories by `DEVTYPE=partition` in its `uevent`
+// Virtual devices (loop, zram, dm, ...) don't have a `device/model`
 #[derive(Debug)]
 pub struct SysBlockDeviceEntries {
-    pub model: String,
+    pub kind: DeviceKind,
+    pub model: Option<String>,
+    pub identity: Option<HardwareIdentity>,
+    pub scsi: Option<ScsiInfo>, // SATA, SAS, USB storage, ... but not NVMe or virtio
     pub size: u64,
     pub removable: bool,
+    pub medium: MediumState,
+    pub devnum: Option<DevNum>,
+    pub diskseq: Option<u64>, // a new number means the disk was replaced under the same name
+    pub loop_info: Option<LoopInfo>,
+    pub dm_info: Option<DmInfo>,
+    pub md_info: Option<MdInfo>,
+    pub bcache: Option<BcacheInfo>,
+    pub nvme: Option<NvmeNamespace>,
+    pub queue: Option<QueueInfo>,
+    pub zoned: Option<ZonedInfo>, // `None` for conventional devices
+    pub holders: Vec<String>,     // devices built on top of this one, e.g. `["dm-0"]`
+    pub slaves: Vec<String>,      // devices this one is built on, e.g. `["sdb1", "sdc1"]`
 }
 
 // `/sys/block/{device}/{partition}/` entries
@@ -27,6 +97,13 @@ pub struct SysBlockDeviceEntries {
 pub struct SysBlockPartitionEntries {
     pub size: u64,
     pub removable: bool,
+    pub devnum: Option<DevNum>,
+    pub partname: Option<String>, // GPT partition name, e.g. `EFI System Partition`
+    pub geometry: PartitionGeometry,
+    pub queue: Option<QueueInfo>,
+    pub bcache: Option<BcacheInfo>,
+    pub holders: Vec<String>,
+    pub slaves: Vec<String>,
 }
 
 // Information abstraction for each partition
@@ -45,30 +122,67 @@ pub struct SysBlockDevice {
 }
 
 // Pack all the information
-#[derive(Debug)]
+// A device or partition that can't be read is left out and its error
+//     is kept in `diagnostics`, so one bad entry doesn't hide the others
+#[derive(Debug, Default)]
 pub struct SysBlockInfo {
     pub info: Vec<SysBlockDevice>,
+    pub diagnostics: Vec<Error>,
 }
 
 impl SysBlockInfo {
-    pub fn new() -> Result<Self> {
-        let block_devices = SysBlockEntries::new()?;
+    pub fn new(paths: &SysPaths) -> Result<Self> {
+        let block_devices = SysBlockEntries::new(&paths.sys_block)?;
         let mut info = Vec::new();
+        let mut diagnostics = Vec::new();
 
         for device_path in &block_devices.block_devices {
-            info.push(SysBlockDevice::new(device_path)?);
+            match SysBlockDevice::new(device_path, &mut diagnostics) {
+                Ok(device) => info.push(device),
+                Err(e) => diagnostics.push(e),
+            }
         }
 
-        Ok(Self { info })
+        Ok(Self { info, diagnostics })
     }
 }
 
+// Kernel name of a device given by the user, e.g. `sda1`, `/dev/sda1`,
+//     `cryptroot` or `/dev/mapper/cryptroot` (the last two for `dm-0`)
+// Names that don't match anything are returned without their `/dev/` prefix
+pub fn resolve_device_name(paths: &SysPaths, name: &str) -> String {
+    let name = name.strip_prefix("/dev/").unwrap_or(name);
+    let mapper_name = match name.strip_prefix("mapper/") {
+        Some(mapper_name) => mapper_name,
+        // Kernel names take precedence over mapper names
+        None if paths.sys_block.join(name).exists() => return name.to_string(),
+        None => name,
+    };
+
+    fs::read_dir(&paths.sys_block)
+        .into_iter()
+        .flatten()
+        .filter_map(std::result::Result::ok)
+        .find(|entry| {
+            read_optional_attr(&entry.path().join("dm/name"))
+                .is_ok_and(|dm_name| dm_name.as_deref() == Some(mapper_name))
+        })
+        .map_or_else(
+            || name.to_string(),
+            |entry| entry.file_name().to_string_lossy().into_owned(),
+        )
+}
+
 impl SysBlockEntries {
-    pub fn new() -> Result<Self> {
+    pub fn new(sys_block: &Path) -> Result<Self> {
         let mut block_devices = Vec::<PathBuf>::new();
 
-        for entry in fs::read_dir("/sys/block")? {
-            let device_name: PathBuf = entry?.path();
+        let entries =
+            fs::read_dir(sys_block).map_err(|e| Error::io(Source::SysBlock, sys_block, e))?;
+        for entry in entries {
+            let device_name: PathBuf = entry
+                .map_err(|e| Error::io(Source::SysBlock, sys_block, e))?
+                .path();
             block_devices.push(device_name);
         }
 
@@ -77,32 +191,106 @@ impl SysBlockEntries {
 }
 
 impl SysBlockDevice {
-    pub fn new(block_device: &Path) -> Result<Self> {
+    // Partitions that fail to be read are reported through `diagnostics`
+    pub fn new(block_device: &Path, diagnostics: &mut Vec<Error>) -> Result<Self> {
         // Extract device name from Path
         let name = block_device
             .file_name()
-            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Invalid device path"))?
+            .ok_or_else(|| {
+                Error::parse(
+                    Source::SysBlock,
+                    block_device,
+                    "Invalid device path".to_string(),
+                )
+            })?
             .to_string_lossy()
             .to_string();
 
-        let model = read_device_model(block_device)?;
-        let size = read_size(block_device)?;
-        let removable = read_removable(block_device)?;
+        // Details that don't parse are reported without dropping the device
+        let mut details = Vec::new();
+        let model = read_device_model(block_device).map_err(|e| e.with_device(&name))?;
+        let identity = HardwareIdentity::new(block_device).map_err(|e| e.with_device(&name))?;
+        let scsi = ScsiInfo::new(block_device, &mut details).map_err(|e| e.with_device(&name))?;
+        let size = read_size(block_device).map_err(|e| e.with_device(&name))?;
+        let removable = read_removable(block_device).map_err(|e| e.with_device(&name))?;
+        let uevent = Uevent::new(block_device)
+            .map_err(|e| e.with_device(&name))?
+            .unwrap_or_default();
+        let devnum = read_devnum(block_device)
+            .map_err(|e| e.with_device(&name))?
+            .or_else(|| uevent.devnum());
+        let diskseq = uevent.diskseq;
+        let kind = DeviceKind::classify(block_device, &name, devnum);
+        let medium = MediumState::new(block_device, kind, size, removable)
+            .map_err(|e| e.with_device(&name))?;
+        let loop_info =
+            LoopInfo::new(block_device, &mut details).map_err(|e| e.with_device(&name))?;
+        let dm_info = DmInfo::new(block_device).map_err(|e| e.with_device(&name))?;
+        let md_info = MdInfo::new(block_device, &mut details).map_err(|e| e.with_device(&name))?;
+        let bcache = BcacheInfo::new(block_device).map_err(|e| e.with_device(&name))?;
+        let nvme =
+            NvmeNamespace::new(block_device, &mut details).map_err(|e| e.with_device(&name))?;
+        let queue = QueueInfo::new(block_device, &mut details).map_err(|e| e.with_device(&name))?;
+        let zoned = ZonedInfo::new(block_device, &mut details).map_err(|e| e.with_device(&name))?;
+        let holders =
+            read_dir_names(&block_device.join("holders")).map_err(|e| e.with_device(&name))?;
+        let slaves =
+            read_dir_names(&block_device.join("slaves")).map_err(|e| e.with_device(&name))?;
+        diagnostics.extend(details.into_iter().map(|e| e.with_device(&name)));
 
         let info = SysBlockDeviceEntries {
+            kind,      // from the name, major number and sysfs entries
             model,     // from `/sys/block/{device}/device/model`
+            identity,  // from `/sys/block/{device}/device/`
+            scsi,      // from the `/sys/block/{device}/device` path and its SCSI host
             size,      // from `/sys/block/{device}/size`
             removable, // from `/sys/block/{device}/removable`
+            medium,    // from `/sys/block/{device}/{events,ro}` and `device/state`
+            devnum,    // from `/sys/block/{device}/dev`, or `MAJOR` and `MINOR` in `uevent`
+            diskseq,   // from `DISKSEQ` in `/sys/block/{device}/uevent`
+            loop_info, // from `/sys/block/{device}/loop/`
+            dm_info,   // from `/sys/block/{device}/dm/`
+            md_info,   // from `/sys/block/{device}/md/`
+            bcache,    // from `/sys/block/{device}/bcache/` and its cache set
+            nvme,      // from `/sys/block/{device}/{nsid,eui,nguid,uuid}` and `device/`
+            queue,     // from `/sys/block/{device}/queue/`
+            zoned,     // from `/sys/block/{device}/queue/{zoned,nr_zones,...}`
+            holders,   // from `/sys/block/{device}/holders/`
+            slaves,    // from `/sys/block/{device}/slaves/`
         };
 
         // Create partition array from `/sys/block/{device}` entries
-        let partition = fs::read_dir(block_device)?
-            .filter_map(Result::ok)
-            // Into String... So we can use `starts_with`
-            .map(|e| e.file_name().to_string_lossy().to_string())
-            .filter(|entry| entry.starts_with(&name))
-            .map(|part_name| SysBlockPartition::new(block_device, &part_name, removable))
-            .collect::<Result<Vec<SysBlockPartition>>>()?;
+        // Only real directories, `device` and `bdi` are symlinks to other devices
+        let mut partition = Vec::new();
+        let entries = fs::read_dir(block_device)
+            .map_err(|e| Error::io(Source::SysBlock, block_device, e).with_device(&name))?
+            .filter_map(std::result::Result::ok)
+            .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()));
+        for entry in entries {
+            let part_name = entry.file_name().to_string_lossy().into_owned();
+            let uevent = match Uevent::new(&entry.path()) {
+                Ok(Some(uevent)) if uevent.is_partition() => uevent,
+                Ok(_) => continue,
+                Err(e) => {
+                    diagnostics.push(e.with_device(&part_name));
+                    continue;
+                }
+            };
+            let mut details = Vec::new();
+            match SysBlockPartition::new(
+                block_device,
+                &part_name,
+                &uevent,
+                removable,
+                info.queue.as_ref(),
+                &mut details,
+            ) {
+                Ok(part) => partition.push(part),
+                Err(e) => diagnostics.push(e.with_device(&part_name)),
+            }
+            diagnostics.extend(details.into_iter().map(|e| e.with_device(&part_name)));
+        }
+        partition.sort_by(|a, b| a.name.cmp(&b.name));
 
         let part = if partition.is_empty() {
             None
@@ -114,40 +302,489 @@ impl SysBlockDevice {
     }
 }
 
+impl DeviceKind {
+    fn classify(path: &Path, name: &str, devnum: Option<DevNum>) -> Self {
+        let major = devnum.map(|devnum| devnum.major);
+
+        if major == Some(7) || path.join("loop").is_dir() {
+            Self::Loop
+        } else if path.join("dm").is_dir() {
+            Self::Dm
+        } else if major == Some(9) || path.join("md").is_dir() {
+            Self::Md
+        } else if name.starts_with("bcache") && path.join("bcache").exists() {
+            Self::Bcache
+        } else if name.starts_with("zram") {
+            Self::Zram
+        } else if major == Some(43) {
+            Self::Nbd
+        } else if major == Some(11) || is_scsi_rom(path) {
+            Self::Rom
+        } else if major == Some(1) {
+            Self::Ram
+        } else if name.starts_with("mmcblk") && name.contains("boot") {
+            Self::MmcBoot
+        } else {
+            Self::Disk
+        }
+    }
+
+    // Short name, as in the `TYPE` column of `lsblk`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Disk => "disk",
+            Self::Partition => "part",
+            Self::Loop => "loop",
+            Self::Dm => "dm",
+            Self::Md => "md",
+            Self::Bcache => "bcache",
+            Self::Zram => "zram",
+            Self::Nbd => "nbd",
+            Self::Rom => "rom",
+            Self::Ram => "ram",
+            Self::MmcBoot => "mmcboot",
+        }
+    }
+}
+
+impl fmt::Display for DeviceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// `TYPE_ROM` from <scsi/scsi_proto.h>
+fn is_scsi_rom(path: &Path) -> bool {
+    fs::read_to_string(path.join("device/type")).is_ok_and(|t| t.trim() == "5")
+}
+
 impl SysBlockPartition {
-    fn new(dev_path: &Path, part_name: &str, removable: bool) -> Result<Self> {
+    fn new(
+        dev_path: &Path,
+        part_name: &str,
+        uevent: &Uevent,
+        removable: bool,
+        queue: Option<&QueueInfo>,
+        diagnostics: &mut Vec<Error>,
+    ) -> Result<Self> {
         Ok(Self {
             name: part_name.to_string(),
-            info: SysBlockPartitionEntries::new(dev_path, part_name, removable)?,
+            info: SysBlockPartitionEntries::new(
+                dev_path,
+                part_name,
+                uevent,
+                removable,
+                queue,
+                diagnostics,
+            )?,
         })
     }
 }
 
 impl SysBlockPartitionEntries {
-    fn new(dev_path: &Path, part_name: &str, removable: bool) -> Result<Self> {
-        let size = read_size(&dev_path.join(part_name))?;
+    fn new(
+        dev_path: &Path,
+        part_name: &str,
+        uevent: &Uevent,
+        removable: bool,
+        queue: Option<&QueueInfo>,
+        diagnostics: &mut Vec<Error>,
+    ) -> Result<Self> {
+        let part_path = dev_path.join(part_name);
+        let size = read_size(&part_path)?;
+        let mut geometry = PartitionGeometry::new(&part_path, size, diagnostics)?;
+        geometry.number = geometry.number.or(uevent.partn);
         Ok(Self {
             size,      // from `/sys/block/{device}/{partition}/size`
             removable, // propagated from `/sys/block/{device}/removable`
+            // from `/sys/block/{device}/{partition}/dev`, or `MAJOR` and `MINOR` in `uevent`
+            devnum: read_devnum(&part_path)?.or_else(|| uevent.devnum()),
+            partname: uevent.partname.clone(), // from `PARTNAME` in `.../{partition}/uevent`
+            // from `/sys/block/{device}/{partition}/{partition,start,ro,alignment_offset}`
+            //     and `PARTN` in `uevent`
+            geometry,
+            // propagated from `/sys/block/{device}/queue/`
+            queue: queue
+                .map(|q| q.for_partition(&part_path, diagnostics))
+                .transpose()?,
+            bcache: BcacheInfo::new(&part_path)?, // from `.../{partition}/bcache/`
+            holders: read_dir_names(&part_path.join("holders"))?, // from `.../{partition}/holders/`
+            slaves: read_dir_names(&part_path.join("slaves"))?, // from `.../{partition}/slaves/`
         })
     }
 }
 
 fn read_size(path: &Path) -> Result<u64> {
-    let size_str = fs::read_to_string(path.join("size"))?;
+    let size_str = read_attr(&path.join("size"))?;
     size_str
-        .trim()
         .parse::<u64>()
-        .map(|blocks| blocks * 512) // Convert 512-byte blocks to bytes
-        .map_err(|e| Error::new(ErrorKind::InvalidData, e))
+        .map(|sectors| sectors * SECTOR_SIZE)
+        .map_err(|_| Error::parse(Source::SysBlock, path.join("size"), size_str))
 }
 
 fn read_removable(path: &Path) -> Result<bool> {
-    let removable_str = fs::read_to_string(path.join("removable"))?;
-    Ok(removable_str.trim() == "1") // unknown -1, yes 1, not 0
+    let removable_str = read_attr(&path.join("removable"))?;
+    Ok(removable_str == "1") // unknown -1, yes 1, not 0
+}
+
+fn read_device_model(path: &Path) -> Result<Option<String>> {
+    let model = read_optional_attr(&path.join("device/model"))?;
+    Ok(model.filter(|model| !model.is_empty()))
+}
+
+// e.g. `8:0`
+fn read_devnum(path: &Path) -> Result<Option<DevNum>> {
+    read_parsed(&path.join("dev"))
+}
+
+// Entry names of a directory such as `holders/`, sorted
+// A missing directory has no entries
+fn read_dir_names(path: &Path) -> Result<Vec<String>> {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(Error::io(Source::SysBlock, path, e)),
+    };
+
+    let mut names = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|e| Error::io(Source::SysBlock, path, e))?;
+        names.push(entry.file_name().to_string_lossy().into_owned());
+    }
+    names.sort();
+    Ok(names)
+}
+
+// A missing attribute is `None`, one that doesn't parse is an error
+fn read_parsed<T: FromStr>(path: &Path) -> Result<Option<T>> {
+    let Some(value) = read_optional_attr(path)? else {
+        return Ok(None);
+    };
+    value
+        .parse()
+        .map(Some)
+        .map_err(|_| Error::parse(Source::SysBlock, path, value))
+}
+
+// A prefix followed by a number, e.g. `ata3`, `cache0` or `nvme1`
+// With an empty prefix, only a number
+pub(crate) fn numbered(name: &str, prefix: &str) -> bool {
+    name.strip_prefix(prefix)
+        .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+// The names a path is made of, e.g. `["sys", "devices", "pci0000:00"]`
+//     for `/sys/devices/pci0000:00`
+fn path_components(path: &Path) -> Vec<&str> {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect()
+}
+
+// Attributes listing every choice with the active one in brackets, e.g. the
+//     scheduler `mq-deadline [none]` or the bcache mode `[writeback] none`
+// A single choice without brackets is the only one available
+fn selected_choice(choices: &str) -> Option<String> {
+    let choices: Vec<&str> = choices.split_whitespace().collect();
+    choices
+        .iter()
+        .find_map(|choice| choice.strip_prefix('[')?.strip_suffix(']'))
+        .or(match choices[..] {
+            [only] => Some(only),
+            _ => None,
+        })
+        .map(str::to_string)
+}
+
+// For attributes that only add details: a value that doesn't parse is
+//     reported in `diagnostics` and read as `None`, instead of failing the device
+fn read_parsed_or_report<T: FromStr>(
+    path: &Path,
+    diagnostics: &mut Vec<Error>,
+) -> Result<Option<T>> {
+    read_parsed_or_report_from(Source::SysBlock, path, diagnostics)
+}
+
+// Shared with the other sysfs sources, errors are reported under `source`
+pub(crate) fn read_parsed_or_report_from<T: FromStr>(
+    source: Source,
+    path: &Path,
+    diagnostics: &mut Vec<Error>,
+) -> Result<Option<T>> {
+    let Some(value) = read_optional_attr_from(source, path)? else {
+        return Ok(None);
+    };
+    match value.parse() {
+        Ok(parsed) => Ok(Some(parsed)),
+        Err(_) => {
+            diagnostics.push(Error::parse(source, path, value));
+            Ok(None)
+        }
+    }
+}
+
+// `1` for yes, anything else (including a missing attribute) for no
+fn read_flag(path: &Path) -> Result<bool> {
+    Ok(read_optional_attr(path)?.is_some_and(|value| value == "1"))
+}
+
+// Read a sysfs attribute, trimming the trailing newline
+fn read_attr(path: &Path) -> Result<String> {
+    fs::read_to_string(path)
+        .map(|s| s.trim().to_string())
+        .map_err(|e| Error::io(Source::SysBlock, path, e))
+}
+
+// Same as `read_attr`, but a missing attribute isn't an error
+fn read_optional_attr(path: &Path) -> Result<Option<String>> {
+    read_optional_attr_from(Source::SysBlock, path)
 }
 
-fn read_device_model(path: &Path) -> Result<String> {
-    let model_str = fs::read_to_string(path.join("device/model"))?;
-    Ok(model_str.trim().to_string())
+// Shared with the other sysfs sources, errors are reported under `source`
+pub(crate) fn read_optional_attr_from(source: Source, path: &Path) -> Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(s) => Ok(Some(s.trim().to_string())),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Error::io(source, path, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+
+    fn find<'a>(info: &'a SysBlockInfo, name: &str) -> &'a SysBlockDevice {
+        info.info
+            .iter()
+            .find(|device| device.name == name)
+            .unwrap_or_else(|| panic!("`{name}` is missing"))
+    }
+
+    #[test]
+    fn reads_devices_and_partitions_from_root() {
+        let fixture = Fixture::new("sys-block");
+        fixture
+            .file("sys/block/sda/size", "2048\n")
+            .file("sys/block/sda/removable", "0\n")
+            .file("sys/block/sda/dev", "8:0\n")
+            .file("sys/block/sda/uevent", "MAJOR=8\nMINOR=0\nDEVTYPE=disk\n")
+            .file("sys/block/sda/sda1/size", "1024\n")
+            .file("sys/block/sda/sda1/start", "2048\n")
+            .file(
+                "sys/block/sda/sda1/uevent",
+                "MAJOR=8\nMINOR=1\nDEVTYPE=partition\nPARTN=1\n",
+            )
+            .symlink("sys/block/sda/sda1/holders/dm-0", "../../../dm-0");
+
+        let info = SysBlockInfo::new(&fixture.paths()).unwrap();
+        assert!(info.diagnostics.is_empty(), "{:?}", info.diagnostics);
+
+        let sda = find(&info, "sda");
+        assert_eq!(sda.info.kind, DeviceKind::Disk);
+        assert_eq!(sda.info.size, 2048 * 512);
+        assert_eq!(sda.info.devnum, Some(DevNum { major: 8, minor: 0 }));
+
+        let partitions = sda.part.as_ref().unwrap();
+        assert_eq!(partitions.len(), 1);
+        assert_eq!(partitions[0].name, "sda1");
+        assert_eq!(
+            partitions[0].info.devnum,
+            Some(DevNum { major: 8, minor: 1 })
+        );
+        assert_eq!(partitions[0].info.holders, ["dm-0"]);
+    }
+
+    #[test]
+    fn keeps_md_arrays_with_unusual_attributes() {
+        let fixture = Fixture::new("sys-block-md");
+        for (name, dev) in [("md0", "9:0"), ("md1", "9:1")] {
+            fixture
+                .file(&format!("sys/block/{name}/size"), "0\n")
+                .file(&format!("sys/block/{name}/removable"), "0\n")
+                .file(&format!("sys/block/{name}/dev"), dev);
+        }
+        fixture
+            // Reshaping from 3 to 4 members, with a write journal
+            .file("sys/block/md0/md/raid_disks", "4 (3)\n")
+            .file("sys/block/md0/md/sync_completed", "delayed\n")
+            .file("sys/block/md0/md/dev-sdb/slot", "journal\n")
+            .file("sys/block/md0/md/dev-sdb/state", "journal\n")
+            // Inactive, no members yet
+            .file("sys/block/md1/md/raid_disks", "\n")
+            .file("sys/block/md1/md/array_state", "inactive\n");
+
+        let info = SysBlockInfo::new(&fixture.paths()).unwrap();
+        assert!(info.diagnostics.is_empty(), "{:?}", info.diagnostics);
+
+        let md0 = find(&info, "md0").info.md_info.as_ref().unwrap();
+        assert_eq!(md0.raid_disks, Some(4));
+        assert_eq!(md0.sync_completed, None);
+        assert_eq!(md0.members[0].slot, None);
+        assert_eq!(md0.members[0].role(), "journal");
+
+        let md1 = find(&info, "md1").info.md_info.as_ref().unwrap();
+        assert_eq!(md1.raid_disks, None);
+        assert_eq!(md1.health(), MdHealth::Inactive);
+    }
+
+    #[test]
+    fn reports_attributes_that_dont_parse() {
+        let fixture = Fixture::new("sys-block-diagnostics");
+        fixture
+            .file("sys/block/md0/size", "0\n")
+            .file("sys/block/md0/removable", "0\n")
+            .file("sys/block/md0/md/mismatch_cnt", "bogus\n");
+
+        let info = SysBlockInfo::new(&fixture.paths()).unwrap();
+        let md0 = find(&info, "md0");
+        assert_eq!(md0.info.md_info.as_ref().unwrap().mismatch_cnt, None);
+        assert_eq!(info.diagnostics.len(), 1);
+        assert_eq!(info.diagnostics[0].device(), Some("md0"));
+    }
+
+    #[test]
+    fn keeps_devices_whose_details_dont_parse() {
+        let fixture = Fixture::new("sys-block-details");
+        for (name, dev) in [("sda", "8:0"), ("loop0", "7:0")] {
+            fixture
+                .file(&format!("sys/block/{name}/size"), "2048\n")
+                .file(&format!("sys/block/{name}/removable"), "0\n")
+                .file(&format!("sys/block/{name}/dev"), dev);
+        }
+        fixture
+            .file("sys/block/sda/queue/logical_block_size", "512\n")
+            .file("sys/block/sda/queue/optimal_io_size", "bogus\n")
+            .file("sys/block/sda/queue/zoned", "host-managed\n")
+            .file("sys/block/sda/queue/nr_zones", "bogus\n")
+            .file("sys/block/sda/sda1/size", "1024\n")
+            .file("sys/block/sda/sda1/uevent", "DEVTYPE=partition\n")
+            .file("sys/block/sda/sda1/start", "bogus\n")
+            .file("sys/block/sda/sda1/discard_alignment", "bogus\n")
+            .file("sys/block/loop0/loop/offset", "bogus\n");
+
+        let info = SysBlockInfo::new(&fixture.paths()).unwrap();
+        let devices: Vec<Option<&str>> = info.diagnostics.iter().map(Error::device).collect();
+        assert_eq!(
+            devices,
+            [
+                Some("sda"),
+                Some("sda"),
+                Some("sda1"),
+                Some("sda1"),
+                Some("loop0")
+            ]
+        );
+
+        let sda = find(&info, "sda");
+        assert_eq!(sda.info.queue.as_ref().unwrap().logical_block_size, 512);
+        assert_eq!(sda.info.queue.as_ref().unwrap().optimal_io_size, 0);
+        assert_eq!(sda.info.zoned.unwrap().nr_zones, 0);
+        assert_eq!(sda.part.as_ref().unwrap().len(), 1);
+        assert_eq!(
+            find(&info, "loop0").info.loop_info.as_ref().unwrap().offset,
+            0
+        );
+    }
+
+    #[test]
+    fn keeps_devices_that_cant_be_aligned() {
+        let fixture = Fixture::new("sys-block-alignment");
+        fixture
+            .file("sys/block/dm-0/size", "0\n")
+            .file("sys/block/dm-0/removable", "0\n")
+            .file("sys/block/dm-0/dev", "253:0")
+            .file("sys/block/dm-0/dm/name", "cryptroot\n")
+            // Stacked on devices that can't be aligned
+            .file("sys/block/dm-0/alignment_offset", "-1\n")
+            .file("sys/block/dm-0/queue/logical_block_size", "512\n");
+
+        let info = SysBlockInfo::new(&fixture.paths()).unwrap();
+        assert!(info.diagnostics.is_empty(), "{:?}", info.diagnostics);
+
+        let dm0 = find(&info, "dm-0").info.queue.as_ref().unwrap();
+        assert_eq!(dm0.alignment_offset, AlignmentOffset::Misaligned);
+    }
+
+    #[test]
+    fn detects_partitions_from_uevent() {
+        let fixture = Fixture::new("sys-block-uevent");
+        fixture
+            .file("sys/block/sda/size", "2048\n")
+            .file("sys/block/sda/removable", "0\n")
+            .file("sys/block/sda/dev", "8:0\n")
+            .file(
+                "sys/block/sda/uevent",
+                "MAJOR=8\nMINOR=0\nDEVTYPE=disk\nDISKSEQ=9\n",
+            )
+            .file("sys/block/sda/sda1/size", "1024\n")
+            .file("sys/block/sda/sda1/start", "2048\n")
+            .file(
+                "sys/block/sda/sda1/uevent",
+                "MAJOR=8\nMINOR=1\nDEVTYPE=partition\nPARTN=1\nPARTNAME=EFI\n",
+            )
+            // Not every directory named after the disk is a partition
+            .file("sys/block/sda/sdafoo/uevent", "DEVTYPE=disk\n");
+
+        let info = SysBlockInfo::new(&fixture.paths()).unwrap();
+        assert!(info.diagnostics.is_empty(), "{:?}", info.diagnostics);
+
+        let sda = find(&info, "sda");
+        assert_eq!(sda.info.diskseq, Some(9));
+
+        let partitions = sda.part.as_ref().unwrap();
+        assert_eq!(partitions.len(), 1);
+        assert_eq!(partitions[0].info.partname.as_deref(), Some("EFI"));
+        assert_eq!(partitions[0].info.geometry.number, Some(1));
+    }
+
+    #[test]
+    fn picks_the_selected_choice() {
+        let selected = selected_choice;
+        assert_eq!(selected("mq-deadline [bfq] none").as_deref(), Some("bfq"));
+        assert_eq!(selected("[none] mq-deadline").as_deref(), Some("none"));
+        assert_eq!(
+            selected("writethrough [writeback] writearound none").as_deref(),
+            Some("writeback")
+        );
+        assert_eq!(selected("none").as_deref(), Some("none"));
+        assert_eq!(selected("mq-deadline none"), None);
+        assert_eq!(selected(""), None);
+    }
+
+    #[test]
+    fn numbered_names() {
+        assert!(numbered("ata3", "ata"));
+        assert!(numbered("42", ""));
+        assert!(!numbered("ata", "ata"));
+        assert!(!numbered("ata3a", "ata"));
+        assert!(!numbered("", ""));
+    }
+
+    #[test]
+    fn tells_empty_drives_from_unbound_loops() {
+        let fixture = Fixture::new("sys-block-medium");
+        for (name, dev, removable) in [("sr0", "11:0", "1"), ("loop0", "7:0", "0")] {
+            fixture
+                .file(&format!("sys/block/{name}/size"), "0\n")
+                .file(&format!("sys/block/{name}/removable"), removable)
+                .file(&format!("sys/block/{name}/dev"), dev)
+                .file(&format!("sys/block/{name}/events"), "media_change\n");
+        }
+
+        let info = SysBlockInfo::new(&fixture.paths()).unwrap();
+        assert!(info.diagnostics.is_empty(), "{:?}", info.diagnostics);
+
+        let sr0 = find(&info, "sr0");
+        assert_eq!(sr0.info.kind, DeviceKind::Rom);
+        assert!(!sr0.info.medium.present);
+
+        let loop0 = find(&info, "loop0");
+        assert_eq!(loop0.info.kind, DeviceKind::Loop);
+        assert!(loop0.info.medium.present);
+        assert_eq!(loop0.info.medium.events, ["media_change"]);
+    }
 }
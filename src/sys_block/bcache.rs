@@ -0,0 +1,159 @@
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use super::{numbered, read_dir_names, read_optional_attr, read_parsed, selected_choice};
+use crate::error::Result;
+
+// `/sys/block/{device}/bcache/` entries, or `/sys/block/{device}/{partition}/bcache/`
+// The same directory shows up on the three sides of a bcache device:
+//     `bcache{n}` itself (a symlink to its backing device's), the backing
+//     device, and the caching device, whose own has a `set` link instead
+// Members are related through the cache set in `/sys/fs/bcache/{uuid}/`
+#[derive(Debug, Clone)]
+pub struct BcacheInfo {
+    pub role: BcacheRole,                  // which side of the bcache device this is
+    pub cache_set: Option<String>,         // cache set UUID, `None` while detached
+    pub devices: Vec<String>,              // bcache nodes, e.g. `["bcache0"]`
+    pub backing: Vec<String>,              // e.g. `["sdb"]`
+    pub caches: Vec<String>,               // e.g. `["nvme0n1p1"]`
+    pub cache_mode: Option<String>,        // e.g. `writeback`, `writethrough`
+    pub state: Option<String>,             // e.g. `clean`, `dirty`, `no cache`
+    pub dirty_data: Option<String>,        // as printed by the kernel, e.g. `1.2M`
+    pub sequential_cutoff: Option<String>, // larger sequential I/O skips the cache
+    pub stats: Option<BcacheStats>,        // backing side only
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BcacheRole {
+    Device,  // `bcache{n}`
+    Backing, // the slow device being cached
+    Cache,   // the fast device caching it
+}
+
+// `bcache/stats_total/`, since the device was registered
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BcacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub hit_ratio: u64, // percentage
+    pub bypass_hits: u64,
+    pub bypass_misses: u64,
+}
+
+impl BcacheInfo {
+    pub(super) fn new(block_device: &Path) -> Result<Option<Self>> {
+        let path = block_device.join("bcache");
+        let Ok(bcache) = fs::canonicalize(&path) else {
+            return Ok(None);
+        };
+
+        let is_node = fs::symlink_metadata(&path).is_ok_and(|m| m.file_type().is_symlink());
+        let mut info = if bcache.join("set").exists() {
+            Self::empty(BcacheRole::Cache)
+        } else {
+            let mut info = Self::empty(if is_node {
+                BcacheRole::Device
+            } else {
+                BcacheRole::Backing
+            });
+            info.cache_mode = read_optional_attr(&bcache.join("cache_mode"))?
+                .and_then(|modes| selected_choice(&modes));
+            info.state = read_optional_attr(&bcache.join("state"))?;
+            info.dirty_data = read_optional_attr(&bcache.join("dirty_data"))?;
+            info.sequential_cutoff = read_optional_attr(&bcache.join("sequential_cutoff"))?;
+            info.stats = BcacheStats::new(&bcache.join("stats_total"))?;
+            info.backing.extend(owner(&bcache));
+            info.devices.extend(link_name(&bcache.join("dev")));
+            info
+        };
+
+        // Everything else comes from the cache set, when attached
+        let set = fs::canonicalize(bcache.join("set"))
+            .or_else(|_| fs::canonicalize(bcache.join("cache")))
+            .ok();
+        if let Some(set) = set {
+            info.cache_set = set
+                .file_name()
+                .map(|uuid| uuid.to_string_lossy().into_owned());
+            for entry in read_dir_names(&set)? {
+                let member = fs::canonicalize(set.join(&entry)).ok();
+                if numbered(&entry, "cache") {
+                    info.caches.extend(member.as_deref().and_then(owner));
+                } else if numbered(&entry, "bdev") && info.role == BcacheRole::Cache {
+                    info.backing.extend(member.as_deref().and_then(owner));
+                    info.devices
+                        .extend(member.and_then(|m| link_name(&m.join("dev"))));
+                }
+            }
+        }
+
+        for names in [&mut info.devices, &mut info.backing, &mut info.caches] {
+            names.sort();
+            names.dedup();
+        }
+
+        Ok(Some(info))
+    }
+
+    fn empty(role: BcacheRole) -> Self {
+        Self {
+            role,
+            cache_set: None,
+            devices: Vec::new(),
+            backing: Vec::new(),
+            caches: Vec::new(),
+            cache_mode: None,
+            state: None,
+            dirty_data: None,
+            sequential_cutoff: None,
+            stats: None,
+        }
+    }
+}
+
+impl BcacheStats {
+    fn new(path: &Path) -> Result<Option<Self>> {
+        if !path.is_dir() {
+            return Ok(None);
+        }
+        let read =
+            |name: &str| -> Result<u64> { Ok(read_parsed(&path.join(name))?.unwrap_or_default()) };
+
+        Ok(Some(Self {
+            hits: read("cache_hits")?,
+            misses: read("cache_misses")?,
+            hit_ratio: read("cache_hit_ratio")?,
+            bypass_hits: read("cache_bypass_hits")?,
+            bypass_misses: read("cache_bypass_misses")?,
+        }))
+    }
+}
+
+impl BcacheRole {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Device => "device",
+            Self::Backing => "backing",
+            Self::Cache => "cache",
+        }
+    }
+}
+
+impl fmt::Display for BcacheRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// Device owning a `bcache/` directory, e.g. `sdb1` for `.../sdb/sdb1/bcache`
+fn owner(bcache: &Path) -> Option<String> {
+    Some(bcache.parent()?.file_name()?.to_string_lossy().into_owned())
+}
+
+// Last component of a symlink target, e.g. `bcache0`
+fn link_name(link: &Path) -> Option<String> {
+    let target: PathBuf = fs::read_link(link).ok()?;
+    Some(target.file_name()?.to_string_lossy().into_owned())
+}
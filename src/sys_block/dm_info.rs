@@ -0,0 +1,104 @@
+use std::{fmt, path::Path};
+
+use super::{numbered, read_attr, read_flag, read_optional_attr};
+use crate::error::Result;
+
+// `/sys/block/dm-{n}/dm/` entries
+// `dm-{n}` is only the kernel name, users know these devices by their
+//     mapper name (e.g. `/dev/mapper/cryptroot`)
+#[derive(Debug, Clone)]
+pub struct DmInfo {
+    pub name: String,             // e.g. `cryptroot` or `vg0-root`
+    pub uuid: Option<String>,     // e.g. `CRYPT-LUKS2-{uuid}-cryptroot`, empty for none
+    pub suspended: bool,          // I/O is queued until resumed
+    pub target: Option<DmTarget>, // from the UUID prefix
+}
+
+// What created the mapping, as told by the prefix of its UUID
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DmTarget {
+    Crypt,     // `CRYPT-` (e.g. `CRYPT-LUKS2-`), from cryptsetup
+    Lvm,       // `LVM-`, a logical volume
+    Multipath, // `mpath-`
+    Partition, // `part{n}-`, from kpartx
+    Verity,    // `CRYPT-VERITY-` from veritysetup, or `VERITY-`
+}
+
+impl DmInfo {
+    pub(super) fn new(block_device: &Path) -> Result<Option<Self>> {
+        let path = block_device.join("dm");
+        if !path.is_dir() {
+            return Ok(None);
+        }
+
+        let uuid = read_optional_attr(&path.join("uuid"))?.filter(|uuid| !uuid.is_empty());
+        Ok(Some(Self {
+            name: read_attr(&path.join("name"))?,
+            target: uuid.as_deref().and_then(DmTarget::from_uuid),
+            uuid,
+            suspended: read_flag(&path.join("suspended"))?,
+        }))
+    }
+
+    // e.g. `/dev/mapper/cryptroot`
+    pub fn mapper_path(&self) -> String {
+        format!("/dev/mapper/{}", self.name)
+    }
+}
+
+impl DmTarget {
+    // veritysetup goes through libcryptsetup too, so verity comes first
+    pub fn from_uuid(uuid: &str) -> Option<Self> {
+        if uuid.starts_with("CRYPT-VERITY-") || uuid.starts_with("VERITY-") {
+            Some(Self::Verity)
+        } else if uuid.starts_with("CRYPT-") {
+            Some(Self::Crypt)
+        } else if uuid.starts_with("LVM-") {
+            Some(Self::Lvm)
+        } else if uuid.starts_with("mpath-") {
+            Some(Self::Multipath)
+        } else if uuid
+            .split_once('-')
+            .is_some_and(|(head, _)| numbered(head, "part"))
+        {
+            Some(Self::Partition)
+        } else {
+            None
+        }
+    }
+
+    // Short name, as in the `TYPE` column of `lsblk`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Crypt => "crypt",
+            Self::Lvm => "lvm",
+            Self::Multipath => "mpath",
+            Self::Partition => "part",
+            Self::Verity => "verity",
+        }
+    }
+}
+
+impl fmt::Display for DmTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tells_targets_from_their_uuid() {
+        let target = DmTarget::from_uuid;
+        assert_eq!(target("CRYPT-LUKS2-0123-cryptroot"), Some(DmTarget::Crypt));
+        assert_eq!(target("CRYPT-VERITY-0123-root"), Some(DmTarget::Verity));
+        assert_eq!(target("VERITY-0123"), Some(DmTarget::Verity));
+        assert_eq!(target("LVM-abcdef"), Some(DmTarget::Lvm));
+        assert_eq!(target("mpath-3600a0b80"), Some(DmTarget::Multipath));
+        assert_eq!(target("part1-mpath-3600a0b80"), Some(DmTarget::Partition));
+        assert_eq!(target("part-mpath-3600a0b80"), None);
+        assert_eq!(target("something-else"), None);
+    }
+}
@@ -0,0 +1,178 @@
+use std::{fmt, path::Path};
+
+use super::{AlignmentOffset, QueueInfo, SECTOR_SIZE, read_flag, read_parsed_or_report};
+use crate::error::{Error, Result};
+
+// Where a partition lives on its device
+// From `/sys/block/{device}/{partition}/{partition,start,ro,alignment_offset}`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PartitionGeometry {
+    pub number: Option<u32>, // e.g. `1` for `sda1`, missing on old kernels
+    pub start: u64,          // first sector
+    pub end: u64,            // last sector, inclusive
+    pub read_only: bool,
+    pub alignment_offset: AlignmentOffset, // reported by the kernel
+}
+
+// Why a partition isn't aligned, in order of severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Misalignment {
+    Offset(AlignmentOffset), // the kernel reports an alignment offset
+    PhysicalBlock(u64),      // start isn't a multiple of the physical block size
+    OptimalIo(u64),          // start isn't a multiple of the optimal I/O size
+}
+
+impl PartitionGeometry {
+    pub(super) fn new(part_path: &Path, size: u64, diagnostics: &mut Vec<Error>) -> Result<Self> {
+        let start =
+            read_parsed_or_report(&part_path.join("start"), diagnostics)?.unwrap_or_default();
+        Ok(Self {
+            number: read_parsed_or_report(&part_path.join("partition"), diagnostics)?,
+            start,
+            end: (start + size / SECTOR_SIZE).saturating_sub(1),
+            read_only: read_flag(&part_path.join("ro"))?,
+            alignment_offset: read_parsed_or_report(
+                &part_path.join("alignment_offset"),
+                diagnostics,
+            )?
+            .unwrap_or_default(),
+        })
+    }
+
+    // Offset of the first byte on the device
+    pub fn start_bytes(&self) -> u64 {
+        self.start * SECTOR_SIZE
+    }
+
+    // Checked against the queue of the partition's device
+    pub fn misalignment(&self, queue: &QueueInfo) -> Option<Misalignment> {
+        let start = self.start_bytes();
+        if !self.alignment_offset.is_aligned() {
+            Some(Misalignment::Offset(self.alignment_offset))
+        } else if queue.physical_block_size > 0 && !start.is_multiple_of(queue.physical_block_size)
+        {
+            Some(Misalignment::PhysicalBlock(queue.physical_block_size))
+        } else if queue.optimal_io_size > 0 && !start.is_multiple_of(queue.optimal_io_size) {
+            Some(Misalignment::OptimalIo(queue.optimal_io_size))
+        } else {
+            None
+        }
+    }
+}
+
+impl Misalignment {
+    // Short name, for columns and JSON
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Offset(_) => "offset",
+            Self::PhysicalBlock(_) => "phy-sec",
+            Self::OptimalIo(_) => "opt-io",
+        }
+    }
+}
+
+impl fmt::Display for Misalignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Offset(AlignmentOffset::Bytes(offset)) => {
+                write!(f, "{offset} bytes off its natural alignment")
+            }
+            Self::Offset(AlignmentOffset::Misaligned) => {
+                write!(f, "can't be aligned with the devices it's stacked on")
+            }
+            Self::PhysicalBlock(size) => {
+                write!(f, "doesn't start on a {size}B physical block boundary")
+            }
+            Self::OptimalIo(size) => write!(f, "doesn't start on a {size}B optimal I/O boundary"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+
+    // Advanced format drive striped over 64KiB
+    fn queue() -> QueueInfo {
+        QueueInfo {
+            logical_block_size: 512,
+            physical_block_size: 4096,
+            optimal_io_size: 65536,
+            ..QueueInfo::default()
+        }
+    }
+
+    fn starting_at(start: u64) -> PartitionGeometry {
+        PartitionGeometry {
+            start,
+            ..PartitionGeometry::default()
+        }
+    }
+
+    #[test]
+    fn reads_an_inclusive_end() {
+        let fixture = Fixture::new("geometry");
+        fixture
+            .file("sda1/partition", "1\n")
+            .file("sda1/start", "2048\n")
+            .file("sda1/ro", "0\n")
+            .file("sda1/alignment_offset", "0\n");
+
+        let mut diagnostics = Vec::new();
+        let geometry =
+            PartitionGeometry::new(&fixture.root.join("sda1"), 1024 * 512, &mut diagnostics)
+                .unwrap();
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+        assert_eq!(geometry.number, Some(1));
+        assert_eq!(geometry.start, 2048);
+        assert_eq!(geometry.end, 3071);
+        assert_eq!(geometry.start_bytes(), 1024 * 1024);
+    }
+
+    #[test]
+    fn accepts_aligned_partitions() {
+        assert_eq!(starting_at(2048).misalignment(&queue()), None);
+        // Nothing to check against without topology
+        assert_eq!(starting_at(63).misalignment(&QueueInfo::default()), None);
+    }
+
+    #[test]
+    fn flags_starts_off_the_physical_block() {
+        // The DOS-era start at sector 63
+        assert_eq!(
+            starting_at(63).misalignment(&queue()),
+            Some(Misalignment::PhysicalBlock(4096))
+        );
+    }
+
+    #[test]
+    fn flags_starts_off_the_optimal_io_size() {
+        // 4KiB aligned, but not on a 64KiB stripe
+        assert_eq!(
+            starting_at(2056).misalignment(&queue()),
+            Some(Misalignment::OptimalIo(65536))
+        );
+    }
+
+    #[test]
+    fn prefers_the_offset_reported_by_the_kernel() {
+        let geometry = PartitionGeometry {
+            alignment_offset: AlignmentOffset::Bytes(3584),
+            ..starting_at(63)
+        };
+        assert_eq!(
+            geometry.misalignment(&queue()),
+            Some(Misalignment::Offset(AlignmentOffset::Bytes(3584)))
+        );
+
+        let geometry = PartitionGeometry {
+            alignment_offset: AlignmentOffset::Misaligned,
+            ..starting_at(2048)
+        };
+        assert_eq!(
+            geometry.misalignment(&queue()),
+            Some(Misalignment::Offset(AlignmentOffset::Misaligned))
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use std::{fmt, fs, path::Path};
+
+use super::{numbered, path_components, read_optional_attr};
+use crate::error::Result;
+
+// Who made a device and how it's attached, to match it to a physical bay
+// Read from `/sys/block/{device}/device/`, which virtual devices don't have
+#[derive(Debug, Clone, Default)]
+pub struct HardwareIdentity {
+    pub vendor: Option<String>,   // e.g. `ATA`, `Samsung`, `0x1af4` for virtio
+    pub serial: Option<String>,   // from `device/serial`, `serial` or `device/vpd_pg80`
+    pub wwid: Option<String>,     // World Wide Name, e.g. `naa.5000c500a1b2c3d4`
+    pub revision: Option<String>, // firmware, from `device/rev` or `device/firmware_rev`
+    pub driver: Option<String>,   // bound driver, e.g. `sd`, `nvme` or `virtio_blk`
+    pub transport: Option<Transport>,
+}
+
+// Bus the device is attached through, as in the `TRAN` column of `lsblk`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    Sata,
+    Sas,
+    Usb,
+    Nvme,
+    Virtio,
+    Mmc,
+    Scsi, // anything else behind a SCSI host (e.g. iSCSI, FC, virtual HBAs)
+}
+
+impl HardwareIdentity {
+    pub(super) fn new(block_device: &Path) -> Result<Option<Self>> {
+        let device = block_device.join("device");
+        if !device.exists() {
+            return Ok(None);
+        }
+
+        let non_empty = |value: Option<String>| value.filter(|value| !value.is_empty());
+        let first_of = |paths: &[&Path]| -> Result<Option<String>> {
+            for path in paths {
+                if let Some(value) = non_empty(read_optional_attr(path)?) {
+                    return Ok(Some(value));
+                }
+            }
+            Ok(None)
+        };
+
+        Ok(Some(Self {
+            vendor: non_empty(read_optional_attr(&device.join("vendor"))?),
+            serial: first_of(&[&device.join("serial"), &block_device.join("serial")])?
+                .or_else(|| read_vpd_serial(&device.join("vpd_pg80"))),
+            wwid: first_of(&[&block_device.join("wwid"), &device.join("wwid")])?,
+            revision: first_of(&[&device.join("rev"), &device.join("firmware_rev")])?,
+            driver: fs::read_link(device.join("driver"))
+                .ok()
+                .and_then(|driver| Some(driver.file_name()?.to_string_lossy().into_owned())),
+            transport: fs::canonicalize(&device)
+                .ok()
+                .and_then(|path| Transport::from_device_path(&path)),
+        }))
+    }
+}
+
+// Unit Serial Number VPD page (0x80), SCSI disks (SATA behind libata, SAS)
+//     don't have a `serial` attribute
+// A 4-byte header ending with the page length, then the serial in ASCII,
+//     padded with spaces
+// A page that can't be read or doesn't look right is the same as no page
+fn read_vpd_serial(path: &Path) -> Option<String> {
+    let page = fs::read(path).ok()?;
+    let (header, data) = page.split_at_checked(4)?;
+    if header[1] != 0x80 {
+        return None;
+    }
+    let length = usize::from(u16::from_be_bytes([header[2], header[3]]));
+    let serial = String::from_utf8_lossy(&data[..length.min(data.len())]);
+    let serial = serial.trim_matches(|c: char| c == ' ' || c == '\0');
+    (!serial.is_empty()).then(|| serial.to_string())
+}
+
+impl Transport {
+    // From the resolved `device` symlink, e.g.
+    //     `/sys/devices/pci0000:00/0000:00:17.0/ata1/host0/target0:0:0/0:0:0:0`
+    // USB comes first since USB storage also shows up behind a SCSI host
+    pub fn from_device_path(path: &Path) -> Option<Self> {
+        let components = path_components(path);
+        let any = |matches: fn(&str) -> bool| components.iter().any(|name| matches(name));
+
+        if any(|name| numbered(name, "usb")) {
+            Some(Self::Usb)
+        } else if any(|name| name == "nvme" || name == "nvme-subsystem") {
+            Some(Self::Nvme)
+        } else if any(|name| name.starts_with("virtio")) {
+            Some(Self::Virtio)
+        } else if any(|name| name.starts_with("mmc")) {
+            Some(Self::Mmc)
+        } else if any(|name| numbered(name, "ata")) {
+            Some(Self::Sata)
+        } else if any(|name| name.starts_with("end_device-") || name.starts_with("expander-")) {
+            Some(Self::Sas)
+        } else if any(|name| name.starts_with("target")) {
+            Some(Self::Scsi)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Sata => "sata",
+            Self::Sas => "sas",
+            Self::Usb => "usb",
+            Self::Nvme => "nvme",
+            Self::Virtio => "virtio",
+            Self::Mmc => "mmc",
+            Self::Scsi => "scsi",
+        }
+    }
+}
+
+impl fmt::Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+
+    fn vpd_pg80(serial: &str) -> Vec<u8> {
+        let mut page = vec![0x00, 0x80, 0x00, u8::try_from(serial.len()).unwrap()];
+        page.extend_from_slice(serial.as_bytes());
+        page
+    }
+
+    #[test]
+    fn reads_the_serial_of_a_sata_drive_from_its_vpd_page() {
+        let fixture = Fixture::new("identity-sata");
+        let device = "devices/pci0000:00/0000:00:17.0/ata1/host0/target0:0:0/0:0:0:0";
+        fixture
+            .file(&format!("sys/{device}/vendor"), "ATA     \n")
+            .file(&format!("sys/{device}/rev"), "RVT04B6Q\n")
+            .file(
+                &format!("sys/{device}/vpd_pg80"),
+                vpd_pg80("    S3Z9NB0K123456"),
+            )
+            .symlink("sys/block/sda/device", &format!("../../{device}"));
+
+        let identity = HardwareIdentity::new(&fixture.paths().sys_block.join("sda"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(identity.vendor.as_deref(), Some("ATA"));
+        assert_eq!(identity.serial.as_deref(), Some("S3Z9NB0K123456"));
+        assert_eq!(identity.revision.as_deref(), Some("RVT04B6Q"));
+        assert_eq!(identity.transport, Some(Transport::Sata));
+    }
+
+    #[test]
+    fn prefers_the_serial_attribute() {
+        let fixture = Fixture::new("identity-serial");
+        fixture
+            .file("sys/block/vda/serial", "virtio-1234\n")
+            .file("sys/block/vda/device/vpd_pg80", vpd_pg80("ignored"));
+
+        let identity = HardwareIdentity::new(&fixture.paths().sys_block.join("vda"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(identity.serial.as_deref(), Some("virtio-1234"));
+    }
+
+    #[test]
+    fn ignores_malformed_vpd_pages() {
+        let fixture = Fixture::new("identity-vpd");
+        fixture
+            .file("short", [0x00, 0x80])
+            .file("other-page", [0x00, 0x83, 0x00, 0x02, b'a', b'b'])
+            .file("blank", vpd_pg80("    "));
+        for name in ["short", "other-page", "blank", "missing"] {
+            assert_eq!(read_vpd_serial(&fixture.root.join(name)), None, "{name}");
+        }
+    }
+}
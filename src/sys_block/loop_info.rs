@@ -0,0 +1,42 @@
+use std::path::Path;
+
+use super::{read_flag, read_optional_attr, read_parsed_or_report};
+use crate::error::{Error, Result};
+
+// `/sys/block/loop{n}/loop/` entries
+// The directory only exists while the loop device is bound to a file
+#[derive(Debug, Clone)]
+pub struct LoopInfo {
+    pub backing_file: Option<String>, // may end with ` (deleted)`
+    pub offset: u64,                  // bytes into the backing file
+    pub sizelimit: u64,               // bytes, 0 means up to the end of the file
+    pub autoclear: bool,              // detached on last close
+    pub partscan: bool,               // partitions are scanned
+    pub dio: bool,                    // direct I/O on the backing file
+}
+
+impl LoopInfo {
+    pub(super) fn new(block_device: &Path, diagnostics: &mut Vec<Error>) -> Result<Option<Self>> {
+        let path = block_device.join("loop");
+        if !path.is_dir() {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            backing_file: read_optional_attr(&path.join("backing_file"))?,
+            offset: read_parsed_or_report(&path.join("offset"), diagnostics)?.unwrap_or_default(),
+            sizelimit: read_parsed_or_report(&path.join("sizelimit"), diagnostics)?
+                .unwrap_or_default(),
+            autoclear: read_flag(&path.join("autoclear"))?,
+            partscan: read_flag(&path.join("partscan"))?,
+            dio: read_flag(&path.join("dio"))?,
+        }))
+    }
+
+    // Snap and Flatpak-style images, usually mounted read-only as squashfs
+    pub fn is_image(&self) -> bool {
+        self.backing_file
+            .as_deref()
+            .is_some_and(|file| file.ends_with(".snap") || file.contains("/snapd/"))
+    }
+}
@@ -0,0 +1,191 @@
+use std::{fmt, path::Path};
+
+use super::{read_dir_names, read_optional_attr, read_parsed_or_report};
+use crate::error::{Error, Result, Source};
+
+// `/sys/block/md{n}/md/` entries
+// Attributes may be missing depending on the level (e.g. `raid0` has no
+//     `degraded` nor `sync_action`), so most of them are optional
+// Reshaping, resyncing and inactive arrays write some of them differently,
+//     a value that still doesn't parse is reported and left out
+#[derive(Debug, Clone)]
+pub struct MdInfo {
+    pub level: Option<String>,              // e.g. `raid1`, `raid5`, `linear`
+    pub raid_disks: Option<u32>,            // expected number of members, the new one on reshape
+    pub array_state: Option<String>,        // e.g. `clean`, `active`, `inactive`
+    pub degraded: Option<u32>,              // missing members
+    pub sync_action: Option<String>,        // e.g. `idle`, `resync`, `recover`, `check`
+    pub sync_completed: Option<(u64, u64)>, // sectors done and total, not while idle or delayed
+    pub mismatch_cnt: Option<u64>,          // sectors found out of sync by the last check
+    pub members: Vec<MdMember>,             // from every `dev-{member}/`
+}
+
+// `/sys/block/md{n}/md/dev-{member}/` entries
+#[derive(Debug, Clone)]
+pub struct MdMember {
+    pub name: String,       // e.g. `sdb1`
+    pub slot: Option<u32>,  // position in the array, `none` for spares and `journal`
+    pub state: Vec<String>, // e.g. `["in_sync", "write_mostly"]`
+}
+
+// Summary of `array_state`, `degraded` and `sync_action`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MdHealth {
+    Healthy,
+    Degraded, // members are missing, redundancy is reduced or gone
+    Syncing,  // resyncing, recovering, reshaping or checking
+    Inactive, // not started (e.g. assembled with members missing)
+}
+
+impl MdInfo {
+    pub(super) fn new(block_device: &Path, diagnostics: &mut Vec<Error>) -> Result<Option<Self>> {
+        let path = block_device.join("md");
+        if !path.is_dir() {
+            return Ok(None);
+        }
+
+        let mut members = Vec::new();
+        for entry in read_dir_names(&path)? {
+            if let Some(name) = entry.strip_prefix("dev-") {
+                members.push(MdMember::new(&path.join(&entry), name, diagnostics)?);
+            }
+        }
+
+        Ok(Some(Self {
+            level: read_optional_attr(&path.join("level"))?.filter(|level| !level.is_empty()),
+            raid_disks: read_raid_disks(&path.join("raid_disks"), diagnostics)?,
+            array_state: read_optional_attr(&path.join("array_state"))?,
+            degraded: read_parsed_or_report(&path.join("degraded"), diagnostics)?,
+            sync_action: read_optional_attr(&path.join("sync_action"))?,
+            sync_completed: read_sync_completed(&path.join("sync_completed"), diagnostics)?,
+            mismatch_cnt: read_parsed_or_report(&path.join("mismatch_cnt"), diagnostics)?,
+            members,
+        }))
+    }
+
+    pub fn health(&self) -> MdHealth {
+        if matches!(self.array_state.as_deref(), Some("inactive" | "clear")) {
+            MdHealth::Inactive
+        } else if self.degraded.is_some_and(|degraded| degraded > 0) {
+            MdHealth::Degraded
+        } else if self.is_syncing() {
+            MdHealth::Syncing
+        } else {
+            MdHealth::Healthy
+        }
+    }
+
+    pub fn is_syncing(&self) -> bool {
+        self.sync_action
+            .as_deref()
+            .is_some_and(|action| action != "idle" && action != "frozen")
+    }
+
+    // Percentage of the current sync action, if any
+    #[allow(clippy::cast_precision_loss)]
+    pub fn sync_progress(&self) -> Option<f64> {
+        self.sync_completed
+            .filter(|&(_, total)| total > 0)
+            .map(|(done, total)| done as f64 * 100.0 / total as f64)
+    }
+}
+
+impl MdMember {
+    fn new(path: &Path, name: &str, diagnostics: &mut Vec<Error>) -> Result<Self> {
+        let state = read_optional_attr(&path.join("state"))?.unwrap_or_default();
+        // `none` for spares and faulty members, `journal` for a write journal
+        let slot = match read_optional_attr(&path.join("slot"))? {
+            Some(slot) if slot != "none" && slot != "journal" => {
+                let parsed = slot.parse().ok();
+                if parsed.is_none() {
+                    diagnostics.push(Error::parse(Source::SysBlock, path.join("slot"), slot));
+                }
+                parsed
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            slot,
+            state: state
+                .split(',')
+                .filter(|flag| !flag.is_empty())
+                .map(str::to_string)
+                .collect(),
+        })
+    }
+
+    fn has_state(&self, flag: &str) -> bool {
+        self.state.iter().any(|state| state == flag)
+    }
+
+    // Role of the member in the array
+    pub fn role(&self) -> &'static str {
+        if self.has_state("faulty") {
+            "faulty"
+        } else if self.has_state("journal") {
+            "journal"
+        } else if self.has_state("in_sync") {
+            "active"
+        } else if self.slot.is_some() {
+            "rebuilding" // a spare that took a slot and is being recovered
+        } else if self.has_state("spare") {
+            "spare"
+        } else {
+            "unknown"
+        }
+    }
+}
+
+impl MdHealth {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Healthy => "healthy",
+            Self::Degraded => "degraded",
+            Self::Syncing => "syncing",
+            Self::Inactive => "inactive",
+        }
+    }
+}
+
+impl fmt::Display for MdHealth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// e.g. `4`, or `4 (3)` while reshaping from 3 to 4 members, empty for 0
+fn read_raid_disks(path: &Path, diagnostics: &mut Vec<Error>) -> Result<Option<u32>> {
+    let Some(value) = read_optional_attr(path)? else {
+        return Ok(None);
+    };
+    let Some(first) = value.split_whitespace().next() else {
+        return Ok(None);
+    };
+
+    let parsed = first.parse().ok();
+    if parsed.is_none() {
+        diagnostics.push(Error::parse(Source::SysBlock, path, value));
+    }
+    Ok(parsed)
+}
+
+// `{done} / {total}` in sectors, `none` when idle, or `delayed` while the
+//     action waits for another array on the same disks
+fn read_sync_completed(path: &Path, diagnostics: &mut Vec<Error>) -> Result<Option<(u64, u64)>> {
+    let Some(value) = read_optional_attr(path)? else {
+        return Ok(None);
+    };
+    if value == "none" || value == "delayed" {
+        return Ok(None);
+    }
+
+    let parsed = value
+        .split_once('/')
+        .and_then(|(done, total)| Some((done.trim().parse().ok()?, total.trim().parse().ok()?)));
+    if parsed.is_none() {
+        diagnostics.push(Error::parse(Source::SysBlock, path, value));
+    }
+    Ok(parsed)
+}
@@ -0,0 +1,57 @@
+use std::path::Path;
+
+use super::{DeviceKind, read_flag, read_optional_attr};
+use crate::error::Result;
+
+// Whether a drive holds a medium and can be read, for card readers, optical
+//     drives and other devices that stay around while empty
+// From `/sys/block/{device}/{events,ro}` and `device/state`
+#[derive(Debug, Clone, Default)]
+pub struct MediumState {
+    pub present: bool,                // false for an empty drive, which has a size of 0
+    pub events: Vec<String>,          // e.g. `["media_change", "eject_request"]`
+    pub device_state: Option<String>, // e.g. SCSI `running`, `offline` or NVMe `live`
+    pub read_only: bool,              // e.g. a write-protected SD card or a CD-ROM
+}
+
+impl MediumState {
+    pub(super) fn new(
+        block_device: &Path,
+        kind: DeviceKind,
+        size: u64,
+        removable: bool,
+    ) -> Result<Self> {
+        let events: Vec<String> = read_optional_attr(&block_device.join("events"))?
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(str::to_string)
+            .collect();
+
+        // Removable drives and drives that report medium changes are the only ones
+        //     that can be empty, anything else with a size of 0 is unconfigured
+        // Loops report `media_change` whether bound or not, an unbound loop is
+        //     unconfigured rather than empty
+        let has_media = removable
+            || (kind != DeviceKind::Loop && events.iter().any(|event| event == "media_change"));
+
+        Ok(Self {
+            present: size > 0 || !has_media,
+            events,
+            device_state: read_optional_attr(&block_device.join("device/state"))?
+                .filter(|state| !state.is_empty()),
+            read_only: read_flag(&block_device.join("ro"))?,
+        })
+    }
+
+    // The SCSI or NVMe layer won't take I/O, e.g. a reader that was unplugged
+    pub fn is_offline(&self) -> bool {
+        self.device_state
+            .as_deref()
+            .is_some_and(|state| state != "running" && state != "live")
+    }
+
+    // Opening an empty or offline drive fails, or wakes it up for nothing
+    pub fn can_probe(&self) -> bool {
+        self.present && !self.is_offline()
+    }
+}
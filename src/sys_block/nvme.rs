@@ -0,0 +1,60 @@
+use std::{fs, path::Path};
+
+use super::{numbered, read_dir_names, read_optional_attr, read_parsed};
+use crate::error::{Error, Result};
+use crate::nvme::NvmeController;
+
+// NVMe namespace attributes, directly under `/sys/block/nvme{c}n{n}/`
+// `device` points to the controller, or to the subsystem with native
+//     multipath, in which case every controller is a path to the namespace
+#[derive(Debug, Clone)]
+pub struct NvmeNamespace {
+    pub nsid: Option<u32>,                // namespace ID, e.g. `1`
+    pub eui: Option<String>,              // EUI-64, e.g. `00-25-38-5a-01-b2-c3-d4`
+    pub nguid: Option<String>,            // Namespace GUID, 128 bits
+    pub uuid: Option<String>,             // Namespace UUID, if the drive reports one
+    pub controllers: Vec<NvmeController>, // usually just one
+}
+
+impl NvmeNamespace {
+    pub(super) fn new(block_device: &Path, diagnostics: &mut Vec<Error>) -> Result<Option<Self>> {
+        let Some(nsid) = read_parsed(&block_device.join("nsid"))? else {
+            return Ok(None);
+        };
+        // All zeroes when the drive doesn't report an identifier
+        let identifier = |attr: &str| -> Result<Option<String>> {
+            Ok(read_optional_attr(&block_device.join(attr))?
+                .filter(|id| id.bytes().any(|b| b != b'0' && b != b'-')))
+        };
+
+        Ok(Some(Self {
+            nsid: Some(nsid),
+            eui: identifier("eui")?,
+            nguid: identifier("nguid")?,
+            uuid: identifier("uuid")?,
+            controllers: controllers(&block_device.join("device"), diagnostics)?,
+        }))
+    }
+}
+
+// Either the controller itself (e.g. `nvme0`) or the subsystem
+//     (e.g. `nvme-subsys0`) listing its controllers
+fn controllers(device: &Path, diagnostics: &mut Vec<Error>) -> Result<Vec<NvmeController>> {
+    let Ok(resolved) = fs::canonicalize(device) else {
+        return Ok(Vec::new());
+    };
+    let is_subsystem = resolved
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with("nvme-subsys"));
+
+    if !is_subsystem {
+        return Ok(vec![NvmeController::new(device, diagnostics)?]);
+    }
+    let mut controllers = Vec::new();
+    for name in read_dir_names(&resolved)? {
+        if numbered(&name, "nvme") {
+            controllers.push(NvmeController::new(&resolved.join(name), diagnostics)?);
+        }
+    }
+    Ok(controllers)
+}
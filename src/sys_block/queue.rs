@@ -0,0 +1,163 @@
+use std::{fmt, path::Path, str::FromStr};
+
+use super::{read_flag, read_optional_attr, read_parsed_or_report, selected_choice};
+use crate::error::{Error, Result};
+
+// `/sys/block/{device}/queue/` entries, what `lsblk --topology` and
+//     `lsblk --discard` show
+// Partitions share the queue of their device, only the alignment
+//     offsets are their own
+// Sizes are in bytes unless said otherwise
+#[derive(Debug, Clone, Default)]
+pub struct QueueInfo {
+    pub logical_block_size: u64,  // smallest addressable unit, e.g. 512
+    pub physical_block_size: u64, // smallest unit written atomically, e.g. 4096
+    pub minimum_io_size: u64,     // preferred minimum, e.g. RAID chunk size
+    pub optimal_io_size: u64,     // preferred for sustained I/O, 0 when unknown
+    // From `{device}/alignment_offset` and `{device}/discard_alignment`, not `queue/`
+    pub alignment_offset: AlignmentOffset,
+    pub discard_alignment: AlignmentOffset,
+    pub rotational: bool,            // spinning disk
+    pub scheduler: Option<String>,   // the active one, e.g. `mq-deadline` or `none`
+    pub read_ahead_kb: u64,          // in KiB
+    pub nr_requests: u64,            // request queue size
+    pub discard_granularity: u64,    // 0 when discard isn't supported
+    pub discard_max_bytes: u64,      // largest single discard
+    pub write_cache: Option<String>, // `write back` or `write through`
+    pub dax: bool,                   // direct access, bypassing the page cache
+}
+
+// `{device}/alignment_offset` and `{device}/discard_alignment`
+// Devices print them signed, with `-1` when the limits of the devices they're
+//     stacked on can't be aligned, and partitions print the same value unsigned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlignmentOffset {
+    Bytes(u64), // 0 when aligned
+    Misaligned, // `-1`, or `4294967295` for a partition
+}
+
+impl QueueInfo {
+    pub(super) fn new(block_device: &Path, diagnostics: &mut Vec<Error>) -> Result<Option<Self>> {
+        let path = block_device.join("queue");
+        if !path.is_dir() {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            logical_block_size: read_parsed_or_report(
+                &path.join("logical_block_size"),
+                diagnostics,
+            )?
+            .unwrap_or_default(),
+            physical_block_size: read_parsed_or_report(
+                &path.join("physical_block_size"),
+                diagnostics,
+            )?
+            .unwrap_or_default(),
+            minimum_io_size: read_parsed_or_report(&path.join("minimum_io_size"), diagnostics)?
+                .unwrap_or_default(),
+            optimal_io_size: read_parsed_or_report(&path.join("optimal_io_size"), diagnostics)?
+                .unwrap_or_default(),
+            alignment_offset: read_parsed_or_report(
+                &block_device.join("alignment_offset"),
+                diagnostics,
+            )?
+            .unwrap_or_default(),
+            discard_alignment: read_parsed_or_report(
+                &block_device.join("discard_alignment"),
+                diagnostics,
+            )?
+            .unwrap_or_default(),
+            rotational: read_flag(&path.join("rotational"))?,
+            scheduler: read_optional_attr(&path.join("scheduler"))?
+                .and_then(|schedulers| selected_choice(&schedulers)),
+            read_ahead_kb: read_parsed_or_report(&path.join("read_ahead_kb"), diagnostics)?
+                .unwrap_or_default(),
+            nr_requests: read_parsed_or_report(&path.join("nr_requests"), diagnostics)?
+                .unwrap_or_default(),
+            discard_granularity: read_parsed_or_report(
+                &path.join("discard_granularity"),
+                diagnostics,
+            )?
+            .unwrap_or_default(),
+            discard_max_bytes: read_parsed_or_report(&path.join("discard_max_bytes"), diagnostics)?
+                .unwrap_or_default(),
+            write_cache: read_optional_attr(&path.join("write_cache"))?,
+            dax: read_flag(&path.join("dax"))?,
+        }))
+    }
+
+    // Same queue as the device, with the partition's own alignment offsets
+    pub(super) fn for_partition(
+        &self,
+        part_path: &Path,
+        diagnostics: &mut Vec<Error>,
+    ) -> Result<Self> {
+        Ok(Self {
+            alignment_offset: read_parsed_or_report(
+                &part_path.join("alignment_offset"),
+                diagnostics,
+            )?
+            .unwrap_or_default(),
+            discard_alignment: read_parsed_or_report(
+                &part_path.join("discard_alignment"),
+                diagnostics,
+            )?
+            .unwrap_or_default(),
+            ..self.clone()
+        })
+    }
+}
+
+impl AlignmentOffset {
+    pub fn is_aligned(self) -> bool {
+        self == Self::Bytes(0)
+    }
+
+    // Same as `lsblk`, `-1` when misaligned
+    pub fn as_i64(self) -> i64 {
+        match self {
+            Self::Bytes(bytes) => i64::try_from(bytes).unwrap_or(i64::MAX),
+            Self::Misaligned => -1,
+        }
+    }
+}
+
+impl Default for AlignmentOffset {
+    fn default() -> Self {
+        Self::Bytes(0)
+    }
+}
+
+impl FromStr for AlignmentOffset {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.parse::<i64>().map_err(|_| ())? {
+            -1 => Ok(Self::Misaligned),
+            offset if offset == i64::from(u32::MAX) => Ok(Self::Misaligned),
+            offset => u64::try_from(offset).map(Self::Bytes).map_err(|_| ()),
+        }
+    }
+}
+
+impl fmt::Display for AlignmentOffset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_i64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_signed_and_unsigned_alignment_offsets() {
+        assert_eq!("0".parse(), Ok(AlignmentOffset::Bytes(0)));
+        assert_eq!("3584".parse(), Ok(AlignmentOffset::Bytes(3584)));
+        assert_eq!("-1".parse(), Ok(AlignmentOffset::Misaligned));
+        assert_eq!("4294967295".parse(), Ok(AlignmentOffset::Misaligned));
+        assert!("-2".parse::<AlignmentOffset>().is_err());
+        assert_eq!(AlignmentOffset::Misaligned.to_string(), "-1");
+    }
+}
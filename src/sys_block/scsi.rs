@@ -0,0 +1,200 @@
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use super::{numbered, path_components, read_optional_attr, read_parsed_or_report};
+use crate::error::{Error, Result};
+
+// Where a SCSI device (SATA, SAS, USB storage, iSCSI, ...) sits, as shown
+//     by `lsblk -S`
+// From the resolved `/sys/block/{device}/device` symlink, e.g.
+//     `/sys/devices/pci0000:00/0000:00:17.0/ata1/host0/target0:0:0/0:0:0:0`
+#[derive(Debug, Clone)]
+pub struct ScsiInfo {
+    pub address: ScsiAddress,
+    pub device_type: Option<u8>, // peripheral device type, from `device/type`
+    pub host: String,            // e.g. `host0`
+    pub host_driver: Option<String>, // e.g. `ahci`, `mpt3sas`, `usb-storage`
+    pub adapter: Option<String>, // PCI address of the HBA, e.g. `0000:00:17.0`
+    pub port: Option<String>,    // e.g. `ata1`, `port-0:3` for SAS, `1-2` for USB
+}
+
+// Host, channel, target and LUN, the `HCTL` column of `lsblk`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ScsiAddress {
+    pub host: u32,
+    pub channel: u32,
+    pub target: u32,
+    pub lun: u64,
+}
+
+impl ScsiInfo {
+    // `None` for anything that isn't behind a SCSI host (NVMe, virtio, ...)
+    pub(super) fn new(block_device: &Path, diagnostics: &mut Vec<Error>) -> Result<Option<Self>> {
+        let device = block_device.join("device");
+        let Ok(path) = fs::canonicalize(&device) else {
+            return Ok(None);
+        };
+        let Some(address) = path
+            .file_name()
+            .and_then(|name| name.to_str()?.parse::<ScsiAddress>().ok())
+        else {
+            return Ok(None);
+        };
+
+        let components = path_components(&path);
+        let host = format!("host{}", address.host);
+        let Some(host_index) = components.iter().position(|name| *name == host) else {
+            return Ok(None);
+        };
+        let before_host = &components[..host_index];
+
+        // e.g. `/sys/devices/.../host0/scsi_host/host0/proc_name`
+        let host_path: PathBuf = path
+            .ancestors()
+            .find(|ancestor| {
+                ancestor
+                    .file_name()
+                    .is_some_and(|name| name == host.as_str())
+            })
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let host_driver =
+            read_optional_attr(&host_path.join("scsi_host").join(&host).join("proc_name"))?
+                .filter(|name| !name.is_empty() && name != "(null)");
+
+        Ok(Some(Self {
+            address,
+            device_type: read_parsed_or_report(&device.join("type"), diagnostics)?,
+            host_driver,
+            adapter: before_host
+                .iter()
+                .rev()
+                .find(|name| is_pci_address(name))
+                .map(|name| (*name).to_string()),
+            port: components
+                .iter()
+                .rev()
+                .find(|name| is_port(name))
+                .map(|name| (*name).to_string()),
+            host,
+        }))
+    }
+
+    // Same names as the `TYPE` column of `lsblk -S`
+    pub fn type_name(&self) -> Option<&'static str> {
+        let name = match self.device_type? {
+            0x00 => "disk",
+            0x01 => "tape",
+            0x02 => "printer",
+            0x03 => "processor",
+            0x04 => "worm",
+            0x05 => "rom",
+            0x06 => "scanner",
+            0x07 => "mo-disk",
+            0x08 => "changer",
+            0x09 => "comm",
+            0x0c => "raid",
+            0x0d => "enclosure",
+            0x0e => "rbc",
+            0x11 => "osd",
+            0x7f => "no-lun",
+            _ => return None,
+        };
+        Some(name)
+    }
+}
+
+// `H:C:T:L`, e.g. `0:0:0:0`
+impl std::str::FromStr for ScsiAddress {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut parts = s.split(':');
+        let mut next = || parts.next().ok_or(());
+        let address = Self {
+            host: next()?.parse().map_err(|_| ())?,
+            channel: next()?.parse().map_err(|_| ())?,
+            target: next()?.parse().map_err(|_| ())?,
+            lun: next()?.parse().map_err(|_| ())?,
+        };
+        if parts.next().is_some() {
+            return Err(());
+        }
+        Ok(address)
+    }
+}
+
+impl fmt::Display for ScsiAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}:{}",
+            self.host, self.channel, self.target, self.lun
+        )
+    }
+}
+
+// e.g. `0000:00:17.0`
+fn is_pci_address(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    bytes.len() == 12
+        && bytes[4] == b':'
+        && bytes[7] == b':'
+        && bytes[10] == b'.'
+        && bytes
+            .iter()
+            .enumerate()
+            .all(|(i, b)| matches!(i, 4 | 7 | 10) || b.is_ascii_hexdigit())
+}
+
+// `ata1` (SATA), `port-0:3` or `port-0:0:3` behind an expander (SAS), or
+//     `1-2.4` (USB, bus then hub ports)
+fn is_port(name: &str) -> bool {
+    let digits = |s: &str| numbered(s, "");
+    if name.starts_with("ata") {
+        return numbered(name, "ata");
+    }
+    if let Some(port) = name.strip_prefix("port-") {
+        return port.contains(':') && port.split(':').all(digits);
+    }
+    name.split_once('-')
+        .is_some_and(|(bus, ports)| digits(bus) && ports.split('.').all(digits))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hctl_addresses() {
+        let address: ScsiAddress = "2:0:3:1".parse().unwrap();
+        assert_eq!(
+            address,
+            ScsiAddress {
+                host: 2,
+                channel: 0,
+                target: 3,
+                lun: 1,
+            }
+        );
+        assert_eq!(address.to_string(), "2:0:3:1");
+
+        assert!("0:0:0".parse::<ScsiAddress>().is_err());
+        assert!("0:0:0:0:0".parse::<ScsiAddress>().is_err());
+        assert!("host0:0:0:0".parse::<ScsiAddress>().is_err());
+        assert!("".parse::<ScsiAddress>().is_err());
+    }
+
+    #[test]
+    fn recognizes_ports() {
+        assert!(is_port("ata1"));
+        assert!(is_port("port-0:3"));
+        assert!(is_port("port-2:0:3"));
+        assert!(is_port("1-2.4"));
+        assert!(!is_port("port-0"));
+        assert!(!is_port("end_device-2:0:3"));
+        assert!(!is_port("host0"));
+    }
+}
@@ -0,0 +1,58 @@
+use std::{path::Path, str::FromStr};
+
+use super::read_optional_attr;
+use crate::devnum::DevNum;
+use crate::error::{Error, Result, Source};
+
+// `/sys/block/{device}/uevent` and `/sys/block/{device}/{partition}/uevent`
+// `KEY=value` lines, as sent to udev, unknown keys are ignored
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Uevent {
+    pub major: Option<u32>,
+    pub minor: Option<u32>,
+    pub devtype: Option<String>,  // `disk` or `partition`
+    pub partn: Option<u32>,       // partitions only, e.g. `1`
+    pub partname: Option<String>, // partitions only, the GPT partition name
+    pub diskseq: Option<u64>,     // disks only, grows on every (re)attach
+}
+
+impl Uevent {
+    // `None` when there's no `uevent` (e.g. `holders/`, `queue/`)
+    pub(super) fn new(path: &Path) -> Result<Option<Self>> {
+        let path = path.join("uevent");
+        let Some(content) = read_optional_attr(&path)? else {
+            return Ok(None);
+        };
+
+        let mut uevent = Self::default();
+        for (key, value) in content.lines().filter_map(|line| line.split_once('=')) {
+            match key {
+                "MAJOR" => uevent.major = Some(parse(&path, value)?),
+                "MINOR" => uevent.minor = Some(parse(&path, value)?),
+                "DEVTYPE" => uevent.devtype = Some(value.to_string()),
+                "PARTN" => uevent.partn = Some(parse(&path, value)?),
+                "PARTNAME" => uevent.partname = Some(value.to_string()).filter(|v| !v.is_empty()),
+                "DISKSEQ" => uevent.diskseq = Some(parse(&path, value)?),
+                _ => {}
+            }
+        }
+        Ok(Some(uevent))
+    }
+
+    pub fn is_partition(&self) -> bool {
+        self.devtype.as_deref() == Some("partition")
+    }
+
+    pub fn devnum(&self) -> Option<DevNum> {
+        Some(DevNum {
+            major: self.major?,
+            minor: self.minor?,
+        })
+    }
+}
+
+fn parse<T: FromStr>(path: &Path, value: &str) -> Result<T> {
+    value
+        .parse()
+        .map_err(|_| Error::parse(Source::SysBlock, path, value))
+}
@@ -0,0 +1,76 @@
+use std::{fmt, path::Path};
+
+use super::{SECTOR_SIZE, read_optional_attr, read_parsed_or_report};
+use crate::error::{Error, Result};
+
+// Filesystems that know how to write sequentially within zones
+pub const ZONE_AWARE_FILESYSTEMS: &[&str] = &["btrfs", "f2fs", "zonefs"];
+
+// `/sys/block/{device}/queue/` zone entries, for SMR drives and ZNS namespaces
+// Only present when `queue/zoned` isn't `none`
+#[derive(Debug, Clone, Copy)]
+pub struct ZonedInfo {
+    pub model: ZoneModel,
+    pub nr_zones: u64,
+    pub zone_size: u64,             // bytes, from `chunk_sectors`
+    pub max_open_zones: u64,        // 0 means no limit
+    pub max_active_zones: u64,      // 0 means no limit
+    pub zone_append_max_bytes: u64, // 0 when zone append isn't supported
+}
+
+// `queue/zoned`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZoneModel {
+    HostAware,   // random writes still work, at a cost
+    HostManaged, // writes must be sequential within a zone, conventional filesystems fail
+}
+
+impl ZonedInfo {
+    pub(super) fn new(block_device: &Path, diagnostics: &mut Vec<Error>) -> Result<Option<Self>> {
+        let path = block_device.join("queue");
+        let model = match read_optional_attr(&path.join("zoned"))?.as_deref() {
+            Some("host-aware") => ZoneModel::HostAware,
+            Some("host-managed") => ZoneModel::HostManaged,
+            _ => return Ok(None),
+        };
+        let chunk_sectors: u64 =
+            read_parsed_or_report(&path.join("chunk_sectors"), diagnostics)?.unwrap_or_default();
+
+        Ok(Some(Self {
+            model,
+            nr_zones: read_parsed_or_report(&path.join("nr_zones"), diagnostics)?
+                .unwrap_or_default(),
+            zone_size: chunk_sectors * SECTOR_SIZE,
+            max_open_zones: read_parsed_or_report(&path.join("max_open_zones"), diagnostics)?
+                .unwrap_or_default(),
+            max_active_zones: read_parsed_or_report(&path.join("max_active_zones"), diagnostics)?
+                .unwrap_or_default(),
+            zone_append_max_bytes: read_parsed_or_report(
+                &path.join("zone_append_max_bytes"),
+                diagnostics,
+            )?
+            .unwrap_or_default(),
+        }))
+    }
+
+    // A filesystem that will fail on this device, e.g. `ext4` on a host-managed drive
+    pub fn rejects_filesystem(&self, filesystem: &str) -> bool {
+        self.model == ZoneModel::HostManaged && !ZONE_AWARE_FILESYSTEMS.contains(&filesystem)
+    }
+}
+
+impl ZoneModel {
+    // Same as `queue/zoned` and the `ZONED` column of `lsblk`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::HostAware => "host-aware",
+            Self::HostManaged => "host-managed",
+        }
+    }
+}
+
+impl fmt::Display for ZoneModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}